@@ -1,20 +1,79 @@
 use chrono::{DateTime, Utc};
-use reqwest::blocking::Client;
-use serde::Deserialize;
-use serde_json::{Value, to_vec, from_slice};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, from_slice};
 use std::error::Error;
-use cacache;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::payload::Event;
 
-#[derive(Deserialize, Debug, Clone)]
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Event pages change whenever new activity happens, so they are only
+/// trusted briefly before being revalidated.
+const EVENTS_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Search results for a fixed date range rarely change, but new activity
+/// inside the range should still show up eventually.
+const SEARCH_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How many times a request is retried after a rate limit or server error.
+const MAX_RETRIES: u32 = 5;
+
+/// Longest we are willing to sleep waiting for a rate limit to reset.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(from = "RawEvent", into = "RawEvent")]
 pub struct GitHubEvent {
+    pub id: String,
     pub created_at: String,
-    #[serde(rename = "type")]
-    pub event_type: String,
     pub repo: Repository,
-    pub payload: Value,
+    pub payload: Event,
+    /// The payload exactly as the API sent it, written back when the event
+    /// is serialized so the archive keeps fields `Event` doesn't model.
+    /// `None` for events other sources make up.
+    pub raw_payload: Option<Value>,
+}
+
+/// An event as it appears on the wire, before its payload is typed.
+#[derive(Deserialize, Serialize)]
+struct RawEvent {
+    id: String,
+    created_at: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    repo: Repository,
+    #[serde(default)]
+    payload: Value,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+impl From<RawEvent> for GitHubEvent {
+    fn from(raw: RawEvent) -> Self {
+        GitHubEvent {
+            id: raw.id,
+            created_at: raw.created_at,
+            repo: raw.repo,
+            payload: Event::from_parts(raw.event_type, raw.payload.clone()),
+            raw_payload: Some(raw.payload),
+        }
+    }
+}
+
+impl From<GitHubEvent> for RawEvent {
+    fn from(event: GitHubEvent) -> Self {
+        let (event_type, payload) = event.payload.to_parts();
+        let payload = event.raw_payload.unwrap_or(payload);
+        RawEvent { id: event.id, created_at: event.created_at, event_type, repo: event.repo, payload }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Repository {
     pub name: String,
 }
@@ -23,96 +82,472 @@ pub struct Repository {
 pub struct CommitDetail {
     pub sha: String,
     pub commit: CommitInfo,
+    /// The commit's page on GitHub. Local commits don't have one.
+    #[serde(default)]
+    pub html_url: Option<String>,
+    /// The GitHub account the commit's author email belongs to, if any.
+    #[serde(default)]
+    pub author: Option<GitHubUser>,
+    /// Line counts for the whole commit. Only the commits endpoint has them.
+    #[serde(default)]
+    pub stats: Option<CommitStats>,
+    /// Changed files, which the API caps at 300 per commit.
+    #[serde(default)]
+    pub files: Vec<CommitFile>,
+    /// Other branches the same change was pushed to later in the range.
+    #[serde(skip)]
+    pub also_pushed_to: Vec<String>,
+    /// The author's name when it's someone other than the report's user.
+    #[serde(skip)]
+    pub other_author: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct CommitInfo {
     pub message: String,
+    #[serde(default)]
+    pub author: Option<GitAuthor>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitHubUser {
+    pub login: String,
+}
+
+/// The author recorded in the commit itself.
+#[derive(Deserialize, Debug)]
+pub struct GitAuthor {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommitStats {
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CommitFile {
+    pub filename: String,
+    #[serde(default)]
+    pub additions: u64,
+    #[serde(default)]
+    pub deletions: u64,
+    /// The diff, left out for binary and very large files.
+    #[serde(default)]
+    pub patch: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct PullRequestDetail {
     pub number: i32,
     pub title: String,
+    #[allow(dead_code)]
     pub body: Option<String>,
     pub state: String,
     pub merged: bool,
     pub html_url: String,
 }
 
-pub fn create_client() -> Result<Client, Box<dyn Error>> {
-    let client = reqwest::blocking::Client::builder()
+/// Everything needed to talk to one GitHub API host: the HTTP client,
+/// the base URL (github.com or an Enterprise Server) and the token.
+pub struct ApiClient {
+    pub http: Client,
+    pub base_url: String,
+    pub token: String,
+    pub cache: CacheConfig,
+    rate_limit: Mutex<RateLimit>,
+    slots: RequestSlots,
+}
+
+/// How the response cache is used for this run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Serve fresh entries from cache, fetch or revalidate stale ones.
+    Normal,
+    /// Ignore cached entries and fetch everything again.
+    Refresh,
+    /// Never touch the network; fail if something isn't cached.
+    Offline,
+}
+
+pub struct CacheConfig {
+    /// Cache directory for this API host and identity, see `cache::namespace_dir`.
+    pub dir: PathBuf,
+    pub mode: CacheMode,
+    /// How long a cached PR is trusted before its state is re-checked.
+    pub pr_ttl: Duration,
+}
+
+/// Latest quota reported by the `X-RateLimit-*` headers, plus a shared
+/// pause so every worker thread backs off once any of them hits a limit.
+#[derive(Default)]
+struct RateLimit {
+    limit: Option<u64>,
+    remaining: Option<u64>,
+    reset: Option<u64>,
+    paused_until: Option<Instant>,
+}
+
+/// Caps how many requests are in flight at once. Commits and PRs are
+/// fetched from every rayon thread, and GitHub's secondary rate limits
+/// punish bursts of concurrent requests.
+struct RequestSlots {
+    free: Mutex<usize>,
+    released: Condvar,
+}
+
+/// A request slot, given back when dropped.
+struct RequestSlot<'a>(&'a RequestSlots);
+
+impl RequestSlots {
+    fn new(count: usize) -> RequestSlots {
+        RequestSlots { free: Mutex::new(count), released: Condvar::new() }
+    }
+
+    /// Waits until fewer than the maximum number of requests are in flight.
+    fn acquire(&self) -> RequestSlot<'_> {
+        let mut free = self.free.lock().unwrap();
+        while *free == 0 {
+            free = self.released.wait(free).unwrap();
+        }
+        *free -= 1;
+        RequestSlot(self)
+    }
+}
+
+impl Drop for RequestSlot<'_> {
+    fn drop(&mut self) {
+        *self.0.free.lock().unwrap() += 1;
+        self.0.released.notify_one();
+    }
+}
+
+impl ApiClient {
+    /// Builds a full endpoint URL from a path like `/users/octocat/events`.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /// Logs the remaining API quota as last reported by GitHub.
+    pub fn log_rate_limit(&self) {
+        let rate_limit = self.rate_limit.lock().unwrap();
+        match (rate_limit.remaining, rate_limit.limit) {
+            (Some(remaining), Some(limit)) => {
+                let reset = rate_limit.reset
+                    .and_then(|reset| DateTime::<Utc>::from_timestamp(reset as i64, 0))
+                    .map(|reset| reset.format("%H:%M:%S UTC").to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                log::info!("GitHub API quota: {} of {} requests remaining (resets at {})", remaining, limit, reset);
+            }
+            _ => log::debug!("No API requests made, quota unknown"),
+        }
+    }
+
+    fn record_rate_limit(&self, headers: &HeaderMap) {
+        let mut rate_limit = self.rate_limit.lock().unwrap();
+        if let Some(limit) = header_u64(headers, "X-RateLimit-Limit") {
+            rate_limit.limit = Some(limit);
+        }
+        if let Some(remaining) = header_u64(headers, "X-RateLimit-Remaining") {
+            rate_limit.remaining = Some(remaining);
+        }
+        if let Some(reset) = header_u64(headers, "X-RateLimit-Reset") {
+            rate_limit.reset = Some(reset);
+        }
+    }
+
+    fn pause_for(&self, wait: Duration) {
+        let mut rate_limit = self.rate_limit.lock().unwrap();
+        let until = Instant::now() + wait;
+        if rate_limit.paused_until.is_none_or(|current| current < until) {
+            rate_limit.paused_until = Some(until);
+        }
+    }
+
+    fn wait_if_paused(&self) {
+        let paused_until = self.rate_limit.lock().unwrap().paused_until;
+        if let Some(until) = paused_until {
+            let now = Instant::now();
+            if until > now {
+                thread::sleep(until - now);
+            }
+        }
+    }
+}
+
+/// A filesystem-safe name for the API host, e.g. `api.github.com` or
+/// `github.example.com_api_v3`, used to keep per-host data apart.
+pub fn host_slug(api_url: &str) -> String {
+    api_url
+        .split("://")
+        .last()
+        .unwrap_or(api_url)
+        .trim_end_matches('/')
+        .replace(['/', ':'], "_")
+}
+
+/// `max_requests` is how many requests may be in flight at once.
+pub fn create_client(base_url: &str, token: &str, cache: CacheConfig, max_requests: usize) -> Result<ApiClient, Box<dyn Error>> {
+    let http = reqwest::blocking::Client::builder()
         .user_agent("rust-github-contributions")
         .build()?;
-    log::debug!("Initialized client with disk caching at ./cache");
-    Ok(client)
+    let base_url = base_url.trim_end_matches('/').to_string();
+    log::debug!("Initialized client for {} with disk caching at {}, at most {} requests at once",
+        base_url, cache.dir.display(), max_requests);
+    Ok(ApiClient {
+        http,
+        base_url,
+        token: token.to_string(),
+        cache,
+        rate_limit: Mutex::new(RateLimit::default()),
+        slots: RequestSlots::new(max_requests),
+    })
 }
 
-fn fetch_and_cache<T: serde::de::DeserializeOwned>(client: &Client, url: &str, token: &str, cache_key: &str) -> Result<T, Box<dyn Error>> {
-    let cache_dir = "./.cache";
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
 
-    // Check cache first
-    if let Ok(cached_data) = cacache::read_sync(cache_dir, cache_key) {
-        let result: T = from_slice(&cached_data)?;
-        log::debug!("Cache hit for {}", url);
-        return Ok(result);
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn now_millis() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis()
+}
+
+/// Exponential backoff (1s, 2s, 4s, ...) with up to a second of jitter so
+/// parallel requests don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let jitter_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos() % 1000;
+    Duration::from_secs(1 << attempt) + Duration::from_millis(jitter_ms as u64)
+}
+
+/// How long to wait before retrying a 403/429, or `None` if the response
+/// is a plain permission error rather than a rate limit.
+fn rate_limit_wait(response: &Response, attempt: u32) -> Option<Duration> {
+    let headers = response.headers();
+    if let Some(retry_after) = header_u64(headers, "Retry-After") {
+        return Some(Duration::from_secs(retry_after));
+    }
+    if header_u64(headers, "X-RateLimit-Remaining") == Some(0) {
+        let reset = header_u64(headers, "X-RateLimit-Reset").unwrap_or_else(now_secs);
+        return Some(Duration::from_secs(reset.saturating_sub(now_secs()) + 1));
+    }
+    // Secondary rate limits don't always send headers, but 429 is unambiguous.
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        return Some(backoff(attempt));
+    }
+    None
+}
+
+fn authorized(api: &ApiClient, request: RequestBuilder) -> RequestBuilder {
+    let request = request.header("Accept", "application/vnd.github.v3+json");
+    if api.token.is_empty() {
+        request
+    } else {
+        request.header("Authorization", format!("Bearer {}", api.token))
     }
+}
+
+/// Sends a GET request, waiting out rate limits and retrying transient
+/// failures. With an `etag` the request is conditional and may come back
+/// as a 304.
+fn send_request(api: &ApiClient, url: &str, etag: Option<&str>) -> Result<Response, Box<dyn Error>> {
+    send_with_retry(api, url, || {
+        let request = authorized(api, api.http.get(url));
+        match etag {
+            Some(etag) => request.header("If-None-Match", etag),
+            None => request,
+        }
+    })
+}
 
-    // Fetch from API
-    let mut request = client.get(url)
-        .header("Accept", "application/vnd.github.v3+json");
-    if !token.is_empty() {
-        request = request.header("Authorization", format!("Bearer {}", token));
+/// POSTs a JSON body and returns the JSON response, with the same rate
+/// limit handling and retries as GET requests. Used for GraphQL.
+pub fn post_json(api: &ApiClient, url: &str, body: &Value) -> Result<Value, Box<dyn Error>> {
+    if api.cache.mode == CacheMode::Offline {
+        return Err(format!("Cannot POST to {} with --offline", url).into());
     }
-    let response = request.send()?;
+    let response = send_with_retry(api, url, || authorized(api, api.http.post(url)).json(body))?;
     if !response.status().is_success() {
         log::error!("API request failed for {}: {}", url, response.status());
         let error_body = response.text()?;
         log::error!("Error response body: {:?}", error_body);
         return Err("API request failed".into());
     }
+    Ok(response.json()?)
+}
 
-    // Cache the response
-    let bytes = response.bytes()?;
-    let result: T = from_slice(&bytes)?;
-    cacache::write_sync(cache_dir, cache_key, &bytes)?;
+/// Sends the request made by `build`, waiting out rate limits and retrying
+/// transient failures. Returns the first response that isn't worth retrying.
+fn send_with_retry(api: &ApiClient, url: &str, build: impl Fn() -> RequestBuilder) -> Result<Response, Box<dyn Error>> {
+    let mut attempt = 0;
+    loop {
+        api.wait_if_paused();
+
+        // The slot is only held while sending, not while backing off.
+        let sent = {
+            let _slot = api.slots.acquire();
+            build().send()
+        };
+        let response = match sent {
+            Ok(response) => response,
+            Err(e) if attempt < MAX_RETRIES && (e.is_timeout() || e.is_connect()) => {
+                let wait = backoff(attempt);
+                log::warn!("Request to {} failed ({}), retrying in {:?}", url, e, wait);
+                thread::sleep(wait);
+                attempt += 1;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        api.record_rate_limit(response.headers());
+
+        let status = response.status();
+        if attempt < MAX_RETRIES && (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS) {
+            if let Some(wait) = rate_limit_wait(&response, attempt) {
+                if wait > MAX_RATE_LIMIT_WAIT {
+                    return Err(format!("Rate limit for {} resets in {}s, not waiting", url, wait.as_secs()).into());
+                }
+                log::warn!("Rate limited on {}, waiting {}s before retrying", url, wait.as_secs());
+                api.pause_for(wait);
+                attempt += 1;
+                continue;
+            }
+        }
+        if attempt < MAX_RETRIES && status.is_server_error() {
+            let wait = backoff(attempt);
+            log::warn!("Server error {} for {}, retrying in {:?}", status, url, wait);
+            thread::sleep(wait);
+            attempt += 1;
+            continue;
+        }
+        return Ok(response);
+    }
+}
+
+/// A response body together with the headers we keep alongside it in the
+/// cache: the ETag to revalidate it and the Link header to paginate.
+struct CachedResponse {
+    body: Vec<u8>,
+    etag: Option<String>,
+    link: Option<String>,
+    /// When the entry was written, in unix milliseconds.
+    fetched_at: u128,
+}
+
+impl CachedResponse {
+    /// Whether the entry is younger than `ttl`. `None` means it never expires.
+    fn is_fresh(&self, ttl: Option<Duration>) -> bool {
+        let Some(ttl) = ttl else { return true };
+        now_millis().saturating_sub(self.fetched_at) < ttl.as_millis()
+    }
+}
+
+fn read_cached(api: &ApiClient, cache_key: &str) -> Option<CachedResponse> {
+    let metadata = cacache::metadata_sync(&api.cache.dir, cache_key).ok()??;
+    let body = cacache::read_sync(&api.cache.dir, cache_key).ok()?;
+    let header = |name: &str| metadata.metadata.get(name).and_then(|v| v.as_str()).map(String::from);
+    Some(CachedResponse { etag: header("etag"), link: header("link"), fetched_at: metadata.time, body })
+}
+
+fn write_cached(api: &ApiClient, cache_key: &str, cached: &CachedResponse) -> Result<(), Box<dyn Error>> {
+    let metadata = serde_json::json!({ "etag": cached.etag, "link": cached.link });
+    let mut writer = cacache::WriteOpts::new()
+        .metadata(metadata)
+        .size(cached.body.len())
+        .open_sync(&api.cache.dir, cache_key)?;
+    writer.write_all(&cached.body)?;
+    writer.commit()?;
+    Ok(())
+}
+
+/// Fetches `url` with a single request. If `cached` is given, its ETag is
+/// sent as `If-None-Match` and a 304 reuses it without spending quota.
+fn fetch_response(api: &ApiClient, url: &str, cache_key: &str, cached: Option<CachedResponse>) -> Result<CachedResponse, Box<dyn Error>> {
+    let etag = cached.as_ref().and_then(|c| c.etag.as_deref());
+    let response = send_request(api, url, etag)?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(mut cached) = cached {
+            log::debug!("Not modified, reusing cache for {}", url);
+            // Rewrite the entry so its age restarts from now.
+            cached.fetched_at = now_millis();
+            write_cached(api, cache_key, &cached)?;
+            return Ok(cached);
+        }
+    }
+    if !response.status().is_success() {
+        log::error!("API request failed for {}: {}", url, response.status());
+        let error_body = response.text()?;
+        log::error!("Error response body: {:?}", error_body);
+        return Err("API request failed".into());
+    }
+
+    let header = |name: &str| response.headers().get(name).and_then(|h| h.to_str().ok()).map(String::from);
+    let etag = header("ETag");
+    let link = header("Link");
+    let fetched_at = now_millis();
+    let fetched = CachedResponse { body: response.bytes()?.to_vec(), etag, link, fetched_at };
+    write_cached(api, cache_key, &fetched)?;
     log::debug!("Fetched and cached {}", url);
-    Ok(result)
+    Ok(fetched)
 }
 
-pub fn fetch_all_events(client: &Client, username: &str, token: &str, start_date: DateTime<Utc>) -> Result<Vec<GitHubEvent>, Box<dyn Error>> {
+/// Returns `url` from the cache if it is younger than `ttl`, otherwise
+/// fetches (or revalidates) it, honouring `--refresh` and `--offline`.
+fn fetch_cached(api: &ApiClient, url: &str, cache_key: &str, ttl: Option<Duration>) -> Result<CachedResponse, Box<dyn Error>> {
+    let cached = match api.cache.mode {
+        CacheMode::Refresh => None,
+        CacheMode::Normal | CacheMode::Offline => read_cached(api, cache_key),
+    };
+    match cached {
+        Some(cached) if api.cache.mode == CacheMode::Offline || cached.is_fresh(ttl) => {
+            log::debug!("Cache hit for {}", url);
+            Ok(cached)
+        }
+        None if api.cache.mode == CacheMode::Offline => {
+            Err(format!("{} is not cached and --offline was given", url).into())
+        }
+        cached => fetch_response(api, url, cache_key, cached),
+    }
+}
+
+/// Pulls the `rel="next"` URL out of a `Link` header.
+fn next_page_url(link: &str) -> Option<String> {
+    link.split(',').find(|s| s.contains("rel=\"next\""))
+        .and_then(|s| s.split(';').next())
+        .and_then(|s| s.trim().strip_prefix('<').and_then(|s| s.strip_suffix('>')))
+        .map(String::from)
+}
+
+pub fn fetch_all_events(api: &ApiClient, username: &str, start_date: DateTime<Utc>) -> Result<Vec<GitHubEvent>, Box<dyn Error>> {
     let mut all_events: Vec<GitHubEvent> = Vec::new();
-    let initial_url = format!("https://api.github.com/users/{}/events?per_page=100", username);
-    let mut page_url = initial_url.clone();
+    let mut page_url = api.url(&format!("/users/{}/events?per_page=100", username));
     let mut has_next = true;
     let mut page_count = 0;
 
     while has_next {
         page_count += 1;
         log::debug!("Fetching page {}: {}", page_count, page_url);
+        // Event pages change as new activity comes in, so they expire quickly;
+        // an unchanged page still comes back as a free 304.
         let cache_key = format!("events:{}", page_url);
-        let page_events: Vec<GitHubEvent> = fetch_and_cache(client, &page_url, token, &cache_key)?;
+        let page = fetch_cached(api, &page_url, &cache_key, Some(EVENTS_TTL))?;
+        let page_events: Vec<GitHubEvent> = from_slice(&page.body)?;
         log::debug!("Events received this page: {}", page_events.len());
         all_events.extend(page_events);
-
-        let response = client.get(&page_url)
-            .header("Accept", "application/vnd.github.v3+json")
-            .header("Authorization", format!("Bearer {}", token))
-            .send()?;
-        let link_header = response.headers().get("Link").map(|h| h.to_str().unwrap_or("").to_string());
-        log::debug!("Link header for page {}: {:?}", page_count, link_header);
+        log::debug!("Link header for page {}: {:?}", page_count, page.link);
 
         has_next = false;
-        if let Some(link_str) = link_header {
-            if link_str.contains("rel=\"next\"") {
-                let next_url = link_str.split(',').find(|s| s.contains("rel=\"next\""))
-                    .and_then(|s| s.split(';').next())
-                    .and_then(|s| s.trim().strip_prefix('<').and_then(|s| s.strip_suffix('>')))
-                    .map(String::from);
-                if let Some(next) = next_url {
-                    page_url = next;
-                    has_next = true;
-                }
+        if let Some(link_str) = page.link {
+            if let Some(next) = next_page_url(&link_str) {
+                page_url = next;
+                has_next = true;
             }
             // Check if we’ve reached the last page
             if !link_str.contains("rel=\"last\"") && all_events.len() >= 300 && !has_next {
@@ -146,14 +581,49 @@ pub fn fetch_all_events(client: &Client, username: &str, token: &str, start_date
     Ok(all_events)
 }
 
-pub fn fetch_commit_detail(client: &Client, token: &str, repo: &str, sha: &str) -> Result<CommitDetail, Box<dyn Error>> {
-    let commit_url = format!("https://api.github.com/repos/{}/commits/{}", repo, sha);
-    let cache_key = format!("commit:{}:{}", repo, sha);
-    fetch_and_cache(client, &commit_url, token, &cache_key)
+/// Fetches one page of Search API results, returning the parsed body and
+/// the URL of the next page, if any.
+pub fn fetch_search_page(api: &ApiClient, url: &str) -> Result<(Value, Option<String>), Box<dyn Error>> {
+    let cache_key = format!("search:{}", url);
+    let page = fetch_cached(api, url, &cache_key, Some(SEARCH_TTL))?;
+    let next = page.link.as_deref().and_then(next_page_url);
+    Ok((from_slice(&page.body)?, next))
+}
+
+pub fn fetch_commit_detail(api: &ApiClient, repo: &str, sha: &str) -> Result<CommitDetail, Box<dyn Error>> {
+    let commit_url = api.url(&format!("/repos/{}/commits/{}", repo, sha));
+    let cache_key = format!("commit:{}", commit_url);
+    // A commit addressed by SHA never changes, so it is cached forever.
+    let response = fetch_cached(api, &commit_url, &cache_key, None)?;
+    Ok(from_slice(&response.body)?)
 }
 
-pub fn fetch_pr_detail(client: &Client, token: &str, repo: &str, number: i64) -> Result<PullRequestDetail, Box<dyn Error>> {
-    let pr_url = format!("https://api.github.com/repos/{}/pulls/{}", repo, number);
-    let cache_key = format!("pr:{}:{}", repo, number);
-    fetch_and_cache(client, &pr_url, token, &cache_key)
-}
\ No newline at end of file
+pub fn fetch_pr_detail(api: &ApiClient, repo: &str, number: i64) -> Result<PullRequestDetail, Box<dyn Error>> {
+    let pr_url = api.url(&format!("/repos/{}/pulls/{}", repo, number));
+    let cache_key = format!("pr:{}", pr_url);
+    let response = fetch_cached(api, &pr_url, &cache_key, Some(api.cache.pr_ttl))?;
+    Ok(from_slice(&response.body)?)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_page_url_finds_the_next_link() {
+        let link = r#"<https://api.github.com/user/1/events?page=2>; rel="next", <https://api.github.com/user/1/events?page=3>; rel="last""#;
+        assert_eq!(next_page_url(link).as_deref(), Some("https://api.github.com/user/1/events?page=2"));
+    }
+
+    #[test]
+    fn next_page_url_is_found_in_any_position() {
+        let link = r#"<https://api.github.com/user/1/events?page=1>; rel="prev", <https://api.github.com/user/1/events?page=3>; rel="next""#;
+        assert_eq!(next_page_url(link).as_deref(), Some("https://api.github.com/user/1/events?page=3"));
+    }
+
+    #[test]
+    fn next_page_url_is_none_on_the_last_page() {
+        let link = r#"<https://api.github.com/user/1/events?page=1>; rel="first", <https://api.github.com/user/1/events?page=2>; rel="prev""#;
+        assert_eq!(next_page_url(link), None);
+        assert_eq!(next_page_url(""), None);
+    }
+}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use crate::api::GitHubEvent;
+use crate::cache::namespace_dir;
+use crate::config::xdg_dir;
+
+/// `$XDG_DATA_HOME/gh-user-summary/archive`, falling back to
+/// `~/.local/share/gh-user-summary/archive`.
+pub fn default_root() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share").join("archive")
+}
+
+/// The archive directory for one API host and identity, laid out like the
+/// cache (`<root>/<host>/<identity>`), so events only a token can see, e.g.
+/// in private repositories, are never read without that token. Pass an
+/// empty token for the public archive.
+pub fn archive_dir(root: &Path, api_url: &str, token: &str) -> PathBuf {
+    namespace_dir(root, api_url, token)
+}
+
+fn event_key(username: &str, id: &str) -> String {
+    format!("event:{}:{}", username.to_lowercase(), id)
+}
+
+/// Adds events that aren't archived yet and returns how many were new.
+/// Events never change once created, so existing ones are left alone.
+pub fn store(dir: &Path, username: &str, events: &[GitHubEvent]) -> Result<usize, Box<dyn Error>> {
+    let mut added = 0;
+    for event in events {
+        let key = event_key(username, &event.id);
+        if cacache::metadata_sync(dir, &key)?.is_some() {
+            continue;
+        }
+        let metadata = serde_json::json!({ "created_at": event.created_at });
+        let mut writer = cacache::WriteOpts::new().metadata(metadata).open_sync(dir, &key)?;
+        std::io::Write::write_all(&mut writer, &serde_json::to_vec(event)?)?;
+        writer.commit()?;
+        added += 1;
+    }
+    log::debug!("Archived {} new events for {} in {}", added, username, dir.display());
+    Ok(added)
+}
+
+/// Archived events for `username` created between `start_date` and
+/// `end_date`, newest first like the API returns them.
+pub fn load(dir: &Path, username: &str, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Result<Vec<GitHubEvent>, Box<dyn Error>> {
+    let prefix = event_key(username, "");
+    let mut seen = HashSet::new();
+    let mut events = Vec::new();
+    for entry in cacache::list_sync(dir) {
+        let entry = entry?;
+        // The index is append-only, so the same key can be listed twice.
+        if !entry.key.starts_with(&prefix) || !seen.insert(entry.key.clone()) {
+            continue;
+        }
+        let in_range = entry.metadata.get("created_at")
+            .and_then(|v| v.as_str())
+            .and_then(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+            .is_some_and(|time| time >= start_date && time <= end_date);
+        if !in_range {
+            continue;
+        }
+        let data = cacache::read_sync(dir, &entry.key)?;
+        events.push(serde_json::from_slice::<GitHubEvent>(&data)?);
+    }
+    events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    log::debug!("Loaded {} archived events for {} from {}", events.len(), username, dir.display());
+    Ok(events)
+}
+
+/// Adds archived events to `events`, skipping ones already present.
+pub fn merge(mut events: Vec<GitHubEvent>, archived: Vec<GitHubEvent>) -> Vec<GitHubEvent> {
+    let ids: HashSet<String> = events.iter().map(|event| event.id.clone()).collect();
+    events.extend(archived.into_iter().filter(|event| !ids.contains(&event.id)));
+    events.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    events
+}
@@ -0,0 +1,110 @@
+use crate::api::CommitDetail;
+
+/// Who counts as the report's user when deciding whose commits a push
+/// contains: their GitHub login plus any email aliases.
+pub struct Identity {
+    login: String,
+    emails: Vec<String>,
+}
+
+impl Identity {
+    pub fn new(login: &str, emails: &[String]) -> Identity {
+        Identity {
+            login: login.to_lowercase(),
+            emails: emails.iter().map(|email| email.trim().to_lowercase()).collect(),
+        }
+    }
+
+    /// Matches the configured aliases and GitHub's noreply addresses,
+    /// `login@users.noreply.github.com` and `12345+login@users.noreply.github.com`.
+    fn matches_email(&self, email: &str) -> bool {
+        let email = email.trim().to_lowercase();
+        if self.emails.contains(&email) {
+            return true;
+        }
+        email
+            .strip_suffix("@users.noreply.github.com")
+            .map(|local| local.rsplit('+').next().unwrap_or(local))
+            .is_some_and(|login| login == self.login)
+    }
+
+    /// Whether the user authored or co-authored `commit`. Commits without
+    /// any author information (from sources that already searched by
+    /// author) are assumed to be theirs.
+    pub fn authored(&self, commit: &CommitDetail) -> bool {
+        let login = commit.author.as_ref().map(|user| &user.login);
+        let email = commit.commit.author.as_ref().map(|author| &author.email);
+        if login.is_none() && email.is_none() {
+            return true;
+        }
+        login.is_some_and(|login| login.to_lowercase() == self.login)
+            || email.is_some_and(|email| self.matches_email(email))
+            || co_author_emails(&commit.commit.message).any(|email| self.matches_email(email))
+    }
+}
+
+/// Emails from `Co-authored-by: Name <email>` trailers.
+fn co_author_emails(message: &str) -> impl Iterator<Item = &str> {
+    message.lines().filter_map(|line| {
+        let (key, value) = line.trim().split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("co-authored-by") {
+            return None;
+        }
+        let (_, email) = value.split_once('<')?;
+        email.split_once('>').map(|(email, _)| email)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn identity() -> Identity {
+        Identity::new("Octo", &[" Octo@Example.com ".to_string()])
+    }
+
+    fn commit(author: Value, email: Option<&str>, message: &str) -> CommitDetail {
+        let git_author = email.map(|email| json!({ "name": "Someone", "email": email }));
+        serde_json::from_value(json!({
+            "sha": "a",
+            "author": author,
+            "commit": { "message": message, "author": git_author },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn authored_by_login_or_email() {
+        let identity = identity();
+        assert!(identity.authored(&commit(json!({ "login": "octo" }), Some("other@example.com"), "Fix")));
+        assert!(identity.authored(&commit(Value::Null, Some("octo@example.com"), "Fix")));
+        assert!(!identity.authored(&commit(json!({ "login": "hubot" }), Some("hubot@example.com"), "Fix")));
+    }
+
+    #[test]
+    fn authored_with_noreply_addresses() {
+        let identity = identity();
+        assert!(identity.authored(&commit(Value::Null, Some("octo@users.noreply.github.com"), "Fix")));
+        assert!(identity.authored(&commit(Value::Null, Some("123+Octo@users.noreply.github.com"), "Fix")));
+        assert!(!identity.authored(&commit(Value::Null, Some("123+hubot@users.noreply.github.com"), "Fix")));
+        assert!(!identity.authored(&commit(Value::Null, Some("octo@example.org"), "Fix")));
+    }
+
+    #[test]
+    fn authored_as_co_author() {
+        let message = "Fix\n\nCo-authored-by: Octo <123+octo@users.noreply.github.com>";
+        assert!(identity().authored(&commit(json!({ "login": "hubot" }), Some("hubot@example.com"), message)));
+    }
+
+    #[test]
+    fn commits_without_author_information_are_assumed_to_be_the_users() {
+        assert!(identity().authored(&commit(Value::Null, None, "Fix")));
+    }
+
+    #[test]
+    fn co_author_emails_reads_every_trailer() {
+        let message = "Fix\n\nsee co-authored-by: docs\nCo-authored-by: A <a@example.com>\n  co-authored-by:B <b@example.com>\nSigned-off-by: C <c@example.com>";
+        assert_eq!(co_author_emails(message).collect::<Vec<_>>(), ["a@example.com", "b@example.com"]);
+    }
+}
@@ -0,0 +1,207 @@
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::api::host_slug;
+use crate::config::xdg_dir;
+
+/// `$XDG_CACHE_HOME/gh-user-summary`, falling back to `~/.cache/gh-user-summary`.
+pub fn default_root() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", ".cache")
+}
+
+/// The cache directory for one API host and identity, laid out as
+/// `<root>/<host>/<identity>`. Each token gets its own directory (named by
+/// a hash of the token, never the token itself) so data fetched with
+/// private access is never served to an unauthenticated run.
+pub fn namespace_dir(root: &Path, api_url: &str, token: &str) -> PathBuf {
+    let identity = if token.is_empty() {
+        "anonymous".to_string()
+    } else {
+        let hash = Sha256::digest(token.as_bytes());
+        let hex: String = hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+        format!("token-{}", hex)
+    };
+    root.join(host_slug(api_url)).join(identity)
+}
+
+/// Every `<host>/<identity>` cache directory under `root`.
+fn namespaces(root: &Path) -> Vec<PathBuf> {
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        let Ok(read_dir) = fs::read_dir(dir) else { return Vec::new() };
+        let mut dirs: Vec<PathBuf> = read_dir
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        dirs
+    };
+    subdirs(root).iter().flat_map(|host| subdirs(host)).collect()
+}
+
+/// A namespace directory relative to the root, for display.
+fn namespace_name(root: &Path, namespace: &Path) -> String {
+    namespace.strip_prefix(root).unwrap_or(namespace).display().to_string()
+}
+
+/// Every live cache entry, oldest first. Entries that fail to load are
+/// logged and skipped.
+fn entries(cache_dir: &Path) -> Vec<cacache::Metadata> {
+    // The index is append-only and `list_sync` returns every version ever
+    // written, including removed ones, so look each key up again to get
+    // its current entry.
+    let keys: HashSet<String> = cacache::list_sync(cache_dir)
+        .filter_map(|entry| match entry {
+            Ok(metadata) => Some(metadata.key),
+            Err(e) => {
+                log::warn!("Skipping unreadable cache index entry: {}", e);
+                None
+            }
+        })
+        .collect();
+    let mut entries: Vec<cacache::Metadata> = keys
+        .iter()
+        .filter_map(|key| cacache::metadata_sync(cache_dir, key).ok().flatten())
+        .collect();
+    entries.sort_by_key(|entry| entry.time);
+    entries
+}
+
+/// The part of a cache key before the first `:`, e.g. `events` or `commit`.
+fn key_prefix(key: &str) -> &str {
+    key.split(':').next().unwrap_or(key)
+}
+
+fn age(entry: &cacache::Metadata) -> Duration {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    Duration::from_millis(now.saturating_sub(entry.time) as u64)
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    let bytes = bytes as f64;
+    if bytes >= 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1} GB", bytes / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024.0 * 1024.0 {
+        format!("{:.1} MB", bytes / (1024.0 * 1024.0))
+    } else if bytes >= 1024.0 {
+        format!("{:.1} KB", bytes / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Removes the given entries, and their content unless another remaining
+/// entry still points at the same data.
+fn remove_entries(cache_dir: &Path, doomed: &[cacache::Metadata]) -> Result<(), Box<dyn Error>> {
+    let doomed_keys: HashSet<&str> = doomed.iter().map(|entry| entry.key.as_str()).collect();
+    let kept_content: HashSet<String> = entries(cache_dir)
+        .iter()
+        .filter(|entry| !doomed_keys.contains(entry.key.as_str()))
+        .map(|entry| entry.integrity.to_string())
+        .collect();
+
+    for entry in doomed {
+        cacache::remove_sync(cache_dir, &entry.key)?;
+        if !kept_content.contains(&entry.integrity.to_string()) {
+            // The content may already be gone, e.g. for a corrupt entry.
+            if let Err(e) = cacache::remove_hash_sync(cache_dir, &entry.integrity) {
+                log::debug!("Could not remove content for {}: {}", entry.key, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn list(root: &Path) -> Result<(), Box<dyn Error>> {
+    for namespace in namespaces(root) {
+        println!("{}:", namespace_name(root, &namespace));
+        for entry in entries(&namespace) {
+            println!("{:>6}  {:>10}  {}", format_age(age(&entry)), format_size(entry.size), entry.key);
+        }
+    }
+    Ok(())
+}
+
+pub fn stats(root: &Path) -> Result<(), Box<dyn Error>> {
+    println!("Cache directory: {}", root.display());
+    for namespace in namespaces(root) {
+        let entries = entries(&namespace);
+        let mut by_prefix: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for entry in &entries {
+            let (count, size) = by_prefix.entry(key_prefix(&entry.key)).or_default();
+            *count += 1;
+            *size += entry.size;
+        }
+
+        println!();
+        println!("{}:", namespace_name(root, &namespace));
+        println!("  Entries: {}", entries.len());
+        println!("  Total size: {}", format_size(entries.iter().map(|entry| entry.size).sum()));
+        if let (Some(oldest), Some(newest)) = (entries.first(), entries.last()) {
+            println!("  Oldest entry: {} old", format_age(age(oldest)));
+            println!("  Newest entry: {} old", format_age(age(newest)));
+        }
+        for (prefix, (count, size)) in by_prefix {
+            println!("    {:<10} {:>6} entries  {:>10}", format!("{}:", prefix), count, format_size(size));
+        }
+    }
+    Ok(())
+}
+
+pub fn clear(root: &Path) -> Result<(), Box<dyn Error>> {
+    for namespace in namespaces(root) {
+        cacache::clear_sync(&namespace)?;
+        println!("Cleared {}", namespace_name(root, &namespace));
+    }
+    Ok(())
+}
+
+pub fn prune(root: &Path, older_than: Duration) -> Result<(), Box<dyn Error>> {
+    for namespace in namespaces(root) {
+        let doomed: Vec<cacache::Metadata> = entries(&namespace)
+            .into_iter()
+            .filter(|entry| age(entry) > older_than)
+            .collect();
+        let freed: usize = doomed.iter().map(|entry| entry.size).sum();
+        remove_entries(&namespace, &doomed)?;
+        println!(
+            "{}: removed {} entries older than {} ({})",
+            namespace_name(root, &namespace), doomed.len(), format_age(older_than), format_size(freed)
+        );
+    }
+    Ok(())
+}
+
+/// Reads back every entry, which checks its content against the stored
+/// hash, and removes the ones that are missing or corrupt.
+pub fn verify(root: &Path) -> Result<(), Box<dyn Error>> {
+    for namespace in namespaces(root) {
+        let entries = entries(&namespace);
+        let total = entries.len();
+        let corrupt: Vec<cacache::Metadata> = entries
+            .into_iter()
+            .filter(|entry| match cacache::read_sync(&namespace, &entry.key) {
+                Ok(_) => false,
+                Err(e) => {
+                    log::warn!("Corrupt cache entry {}: {}", entry.key, e);
+                    true
+                }
+            })
+            .collect();
+        remove_entries(&namespace, &corrupt)?;
+        println!("{}: verified {} entries, removed {} corrupt", namespace_name(root, &namespace), total, corrupt.len());
+    }
+    Ok(())
+}
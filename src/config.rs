@@ -0,0 +1,57 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings read from the config file. Everything is optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Email addresses the user commits with, besides GitHub's noreply
+    /// address. Added to any given with `--author-email`.
+    #[serde(default)]
+    pub author_emails: Vec<String>,
+    /// File extension (or extension-less file name) to language, on top of
+    /// the built-in map, e.g. `tsx = "TypeScript"`.
+    #[serde(default)]
+    pub languages: HashMap<String, String>,
+}
+
+/// This tool's directory under the XDG base directory in `var`, e.g.
+/// `$XDG_CACHE_HOME/gh-user-summary`, falling back to `~/<fallback>` when
+/// the variable is unset or empty.
+pub fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    let base = env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .unwrap_or_else(|| PathBuf::from(fallback));
+    base.join("gh-user-summary")
+}
+
+/// `$XDG_CONFIG_HOME/gh-user-summary/config.toml`, falling back to
+/// `~/.config/gh-user-summary/config.toml`.
+pub fn default_path() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config").join("config.toml")
+}
+
+/// Reads the config file at `path`, or the default one when `path` is
+/// `None`. A missing default file just means no config.
+pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+    let (path, explicit) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => (default_path(), false),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => {
+            log::debug!("No config file at {}", path.display());
+            return Ok(Config::default());
+        }
+        Err(e) => return Err(format!("Cannot read config file {}: {}", path.display(), e).into()),
+    };
+    log::debug!("Reading config from {}", path.display());
+    toml::from_str(&text).map_err(|e| format!("Invalid config file {}: {}", path.display(), e).into())
+}
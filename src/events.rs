@@ -1,20 +1,34 @@
-use chrono::{DateTime, Utc};
-use reqwest::blocking::Client;
-use std::collections::HashMap;
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use rayon::prelude::*;
-use crate::api::{GitHubEvent, CommitDetail, PullRequestDetail, fetch_commit_detail, fetch_pr_detail};
+use sha2::{Digest, Sha256};
+use crate::api::{ApiClient, GitHubEvent, CommitDetail, CommitInfo, GitAuthor, PullRequestDetail, Repository, fetch_commit_detail, fetch_pr_detail};
+use crate::authors::Identity;
+use crate::payload::{Event, PushCommit, PushPayload};
 
+/// An event together with the commit and PR details fetched for it.
+pub type EventDetail = (GitHubEvent, Vec<CommitDetail>, Option<PullRequestDetail>);
+
+/// Events grouped by day, keyed by `YYYY-MM-DD`.
+pub type DailySummaries = HashMap<String, Vec<EventDetail>>;
+
+/// Fetches details for the events in range and groups them by their day
+/// in `tz`. Commits by anyone other than `identity` are dropped, or kept
+/// and marked with their author when `include_others` is set.
 pub fn process_events(
-    client: &Client,
-    token: &str,
+    api: &ApiClient,
     events: Vec<GitHubEvent>,
     start_date: DateTime<Utc>,
     end_date: DateTime<Utc>,
-) -> Result<HashMap<String, Vec<(GitHubEvent, Vec<CommitDetail>, Option<PullRequestDetail>)>>, Box<dyn Error>> {
+    identity: &Identity,
+    include_others: bool,
+    tz: Tz,
+) -> Result<DailySummaries, Box<dyn Error>> {
     log::debug!("Filtering events for range {} to {}", start_date, end_date);
 
-    let processed: Vec<(String, (GitHubEvent, Vec<CommitDetail>, Option<PullRequestDetail>))> = events
+    let processed: Vec<(String, EventDetail)> = events
         .par_iter()
         .filter_map(|event| {
             let event_time = match DateTime::parse_from_rfc3339(&event.created_at) {
@@ -28,36 +42,49 @@ pub fn process_events(
 
             log::debug!(
                 "Event - Time: {}, Type: {}, Repo: {}, In range: {}",
-                event.created_at, event.event_type, event.repo.name, in_range
+                event.created_at, event.payload.type_name(), event.repo.name, in_range
             );
 
             if !in_range {
                 return None;
             }
 
+            let mut event = event.clone();
             let mut commits = Vec::new();
             let mut pr_detail = None;
 
-            if event.event_type == "PushEvent" {
-                if let Some(commits_array) = event.payload.get("commits").and_then(|v| v.as_array()) {
-                    commits = commits_array
+            match &mut event.payload {
+                Event::Push(push) => {
+                    let fetched: Vec<CommitDetail> = push.commits
                         .par_iter()
-                        .filter_map(|commit| {
-                            commit.get("sha").and_then(|v| v.as_str()).map(|sha| {
-                                match fetch_commit_detail(client, token, &event.repo.name, sha) {
-                                    Ok(commit_detail) => Some(commit_detail),
-                                    Err(e) => {
-                                        log::warn!("Skipping commit fetch: {}", e);
-                                        None
-                                    }
+                        .map(|commit| {
+                            match fetch_commit_detail(api, &event.repo.name, &commit.sha) {
+                                Ok(commit_detail) => commit_detail,
+                                Err(e) => {
+                                    log::warn!("Using the push's copy of commit {}: {}", commit.sha, e);
+                                    pushed_commit(commit)
                                 }
-                            }).flatten()
+                            }
                         })
                         .collect();
+                    for mut commit in fetched {
+                        if identity.authored(&commit) {
+                            commits.push(commit);
+                        } else if include_others {
+                            let name = commit.commit.author.as_ref().map(|author| author.name.clone());
+                            commit.other_author = Some(name.unwrap_or_else(|| "someone else".to_string()));
+                            commits.push(commit);
+                        } else {
+                            push.size = push.size.saturating_sub(1);
+                        }
+                    }
+                    if commits.is_empty() && !push.commits.is_empty() {
+                        log::debug!("Leaving out a push to {} with only other authors' commits", event.repo.name);
+                        return None;
+                    }
                 }
-            } else if event.event_type == "PullRequestEvent" {
-                if let Some(number) = event.payload.get("number").and_then(|v| v.as_i64()) {
-                    pr_detail = match fetch_pr_detail(client, token, &event.repo.name, number) {
+                Event::PullRequest(pull_request) => {
+                    pr_detail = match fetch_pr_detail(api, &event.repo.name, pull_request.number) {
                         Ok(pr) => Some(pr),
                         Err(e) => {
                             log::warn!("Skipping PR fetch: {}", e);
@@ -65,19 +92,20 @@ pub fn process_events(
                         }
                     };
                 }
+                _ => {}
             }
 
-            let day_key = event_time.format("%Y-%m-%d").to_string();
+            let day_key = event_time.with_timezone(&tz).format("%Y-%m-%d").to_string();
             log::debug!("Adding event to day: {}", day_key);
-            Some((day_key, (event.clone(), commits, pr_detail)))
+            Some((day_key, (event, commits, pr_detail)))
         })
         .collect();
 
-    let mut daily_summaries: HashMap<String, Vec<(GitHubEvent, Vec<CommitDetail>, Option<PullRequestDetail>)>> = HashMap::new();
-    for (day_key, event_data) in processed {
+    let mut daily_summaries: DailySummaries = HashMap::new();
+    for (day_key, event_data) in dedupe_commits(processed) {
         daily_summaries
             .entry(day_key)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(event_data);
     }
 
@@ -86,4 +114,468 @@ pub fn process_events(
         log::warn!("No events found in the specified range.");
     }
     Ok(daily_summaries)
-}
\ No newline at end of file
+}
+
+/// Groups commits from sources without push events into one push per
+/// repository and day in `tz`. Each commit comes with its repository and
+/// author date (RFC 3339, any offset); `source` prefixes the pushes' IDs.
+pub fn group_commits(
+    source: &str,
+    commits: impl IntoIterator<Item = (String, String, CommitDetail)>,
+    tz: Tz,
+) -> DailySummaries {
+    let mut pushes: HashMap<(String, String), EventDetail> = HashMap::new();
+    for (repo, date, commit) in commits {
+        let time = match DateTime::parse_from_rfc3339(&date) {
+            Ok(time) => time,
+            Err(e) => {
+                log::warn!("Failed to parse commit time {}: {}", date, e);
+                continue;
+            }
+        };
+        // Author dates keep the author's offset; store them in UTC like
+        // every other event time.
+        let created_at = time.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let day = time.with_timezone(&tz).format("%Y-%m-%d").to_string();
+        let (push, push_commits, _) = pushes.entry((day.clone(), repo.clone())).or_insert_with(|| {
+            let event = GitHubEvent {
+                id: format!("{}-commits:{}:{}", source, repo, day),
+                created_at: created_at.clone(),
+                repo: Repository { name: repo.clone() },
+                payload: Event::Push(PushPayload::default()),
+                raw_payload: None,
+            };
+            (event, Vec::new(), None)
+        });
+        // The push is shown at the time of its earliest commit.
+        if created_at < push.created_at {
+            push.created_at = created_at;
+        }
+        push_commits.push(commit);
+    }
+
+    let mut daily_summaries: DailySummaries = HashMap::new();
+    for ((day, _), mut push) in pushes {
+        push.0.payload = Event::Push(PushPayload { size: push.1.len() as u64, ..Default::default() });
+        daily_summaries.entry(day).or_default().push(push);
+    }
+    daily_summaries
+}
+
+/// A commit as the push event lists it, for when its details can't be
+/// fetched, e.g. with `--offline` or for archived events. It has the message
+/// and author but no line counts.
+fn pushed_commit(commit: &PushCommit) -> CommitDetail {
+    let author = commit.author.as_ref().map(|author| GitAuthor { name: author.name.clone(), email: author.email.clone() });
+    CommitDetail {
+        sha: commit.sha.clone(),
+        commit: CommitInfo { message: commit.message.clone(), author },
+        html_url: None,
+        author: None,
+        stats: None,
+        files: Vec::new(),
+        also_pushed_to: Vec::new(),
+        other_author: None,
+    }
+}
+
+/// Like `git patch-id`: a hash of the commit's diff that ignores line
+/// numbers and whitespace, so a rebased or cherry-picked copy of a commit
+/// gets the same ID. `None` when the API left out any file's diff.
+fn patch_id(commit: &CommitDetail) -> Option<String> {
+    let mut files: Vec<_> = commit.files.iter().collect();
+    files.sort_by(|a, b| a.filename.cmp(&b.filename));
+    let mut hasher = Sha256::new();
+    let mut changed_lines = 0;
+    for file in files {
+        hasher.update(file.filename.as_bytes());
+        for line in file.patch.as_deref()?.lines() {
+            if line.starts_with('+') || line.starts_with('-') {
+                let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+                hasher.update(line.as_bytes());
+                hasher.update(b"\n");
+                changed_lines += 1;
+            }
+        }
+    }
+    // Commits that only rename files or change modes all look alike.
+    if changed_lines == 0 {
+        return None;
+    }
+    Some(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// The branch a push went to, e.g. `main` for `refs/heads/main`.
+fn push_branch(event: &GitHubEvent) -> Option<&str> {
+    match &event.payload {
+        Event::Push(push) => push.git_ref.as_deref().map(|git_ref| git_ref.trim_start_matches("refs/heads/")),
+        _ => None,
+    }
+}
+
+/// Keeps each commit only in the earliest push that has it, matching by SHA
+/// anywhere in the range and by patch ID for rebased copies in the same
+/// repository. The branches of later pushes are noted on the kept commit,
+/// and pushes left without commits are dropped. Patch IDs aren't matched
+/// across repositories since bots push the same diff to many of them.
+fn dedupe_commits(mut processed: Vec<(String, EventDetail)>) -> Vec<(String, EventDetail)> {
+    processed.sort_by(|a, b| a.1.0.created_at.cmp(&b.1.0.created_at));
+
+    // Where each commit was first seen, as (event index, commit index).
+    let mut first_seen: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut duplicates: Vec<((usize, usize), (usize, usize))> = Vec::new();
+    for (event_index, (_, (event, commits, _))) in processed.iter().enumerate() {
+        let repo = &event.repo.name;
+        for (commit_index, commit) in commits.iter().enumerate() {
+            let keys = [
+                Some(format!("sha:{}", commit.sha)),
+                patch_id(commit).map(|id| format!("{}:patch:{}", repo, id)),
+            ];
+            match keys.iter().flatten().find_map(|key| first_seen.get(key)) {
+                Some(&original) => duplicates.push(((event_index, commit_index), original)),
+                None => {
+                    for key in keys.into_iter().flatten() {
+                        first_seen.insert(key, (event_index, commit_index));
+                    }
+                }
+            }
+        }
+    }
+    if duplicates.is_empty() {
+        return processed;
+    }
+    log::debug!("Collapsing {} commits pushed more than once", duplicates.len());
+
+    let mut removed: HashSet<(usize, usize)> = HashSet::new();
+    for (duplicate, (event_index, commit_index)) in duplicates {
+        removed.insert(duplicate);
+        let (pushed, original) = (&processed[duplicate.0].1.0, &processed[event_index].1.0);
+        // Branches of another repository, e.g. a fork, are named with it.
+        let branch = push_branch(pushed).map(|branch| {
+            if pushed.repo.name == original.repo.name {
+                branch.to_string()
+            } else {
+                format!("{}:{}", pushed.repo.name, branch)
+            }
+        });
+        let original_branch = push_branch(original).map(str::to_string);
+        let commit = &mut processed[event_index].1.1[commit_index];
+        if let Some(branch) = branch {
+            if Some(&branch) != original_branch.as_ref() && !commit.also_pushed_to.contains(&branch) {
+                commit.also_pushed_to.push(branch);
+            }
+        }
+    }
+
+    processed
+        .into_iter()
+        .enumerate()
+        .filter_map(|(event_index, (day_key, (mut event, commits, pr_detail)))| {
+            if commits.is_empty() {
+                return Some((day_key, (event, commits, pr_detail)));
+            }
+            let before = commits.len();
+            let commits: Vec<CommitDetail> = commits
+                .into_iter()
+                .enumerate()
+                .filter(|(commit_index, _)| !removed.contains(&(event_index, *commit_index)))
+                .map(|(_, commit)| commit)
+                .collect();
+            if commits.is_empty() {
+                return None;
+            }
+            if let Event::Push(push) = &mut event.payload {
+                push.size = push.size.saturating_sub((before - commits.len()) as u64);
+            }
+            Some((day_key, (event, commits, pr_detail)))
+        })
+        .collect()
+}
+
+/// The repository and number a PR or issue event is about (or the review's
+/// ID for reviews), used to spot the same item reported by more than one
+/// source.
+fn item_key(detail: &EventDetail) -> Option<(String, String, i64)> {
+    let event = &detail.0;
+    let number = match &event.payload {
+        Event::PullRequest(pull_request) => pull_request.number,
+        Event::Issues(issues) => issues.issue.number,
+        Event::PullRequestReview(review) => review.review.id? as i64,
+        _ => return None,
+    };
+    Some((event.payload.type_name().to_string(), event.repo.name.clone(), number))
+}
+
+/// A push that only knows how many commits it had, like GraphQL's daily
+/// counts, so it can't be matched to other sources' commits by SHA.
+fn is_count_only(detail: &EventDetail) -> bool {
+    matches!(&detail.0.payload, Event::Push(push) if push.commits.is_empty()) && detail.1.is_empty()
+}
+
+/// Adds `extra` to `daily_summaries`, dropping commits whose SHA is already
+/// present and PRs, issues or reviews already reported by an earlier source.
+/// Pushes that only have a commit count give way to any source with the
+/// actual commits for that repository and day.
+pub fn merge_summaries(daily_summaries: &mut DailySummaries, extra: DailySummaries) {
+    // Only compare against what's already there: one source can legitimately
+    // report the same PR twice, e.g. when it was opened and later merged.
+    let mut seen_shas: HashSet<String> = HashSet::new();
+    let mut seen_items: HashSet<(String, String, i64)> = HashSet::new();
+    let mut pushed: HashSet<(String, String)> = HashSet::new();
+    for (day_key, details) in daily_summaries.iter() {
+        for detail in details {
+            seen_shas.extend(detail.1.iter().map(|commit| commit.sha.clone()));
+            seen_items.extend(item_key(detail));
+            if matches!(detail.0.payload, Event::Push(_)) {
+                pushed.insert((day_key.clone(), detail.0.repo.name.clone()));
+            }
+        }
+    }
+
+    let mut skipped = 0;
+    for (day_key, details) in extra {
+        for mut detail in details {
+            if item_key(&detail).is_some_and(|key| seen_items.contains(&key)) {
+                skipped += 1;
+                continue;
+            }
+            if is_count_only(&detail) && pushed.contains(&(day_key.clone(), detail.0.repo.name.clone())) {
+                skipped += 1;
+                continue;
+            }
+            if !detail.1.is_empty() {
+                let before = detail.1.len();
+                detail.1.retain(|commit| !seen_shas.contains(&commit.sha));
+                skipped += before - detail.1.len();
+                if detail.1.is_empty() {
+                    continue;
+                }
+                if let Event::Push(push) = &mut detail.0.payload {
+                    push.size = detail.1.len() as u64;
+                }
+            }
+            daily_summaries.entry(day_key.clone()).or_default().push(detail);
+        }
+    }
+
+    // Counts from an earlier source are replaced by the commits themselves.
+    for details in daily_summaries.values_mut() {
+        let with_commits: HashSet<String> = details.iter()
+            .filter(|detail| matches!(detail.0.payload, Event::Push(_)) && !is_count_only(detail))
+            .map(|detail| detail.0.repo.name.clone())
+            .collect();
+        let before = details.len();
+        details.retain(|detail| !(is_count_only(detail) && with_commits.contains(&detail.0.repo.name)));
+        skipped += before - details.len();
+    }
+    log::debug!("Merged sources, skipped {} duplicate commits, pushes, PRs, issues and reviews", skipped);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    const DAY: &str = "2024-02-10";
+
+    fn event(id: &str, event_type: &str, repo: &str, created_at: &str, payload: Value) -> GitHubEvent {
+        serde_json::from_value(json!({
+            "id": id,
+            "type": event_type,
+            "repo": { "name": repo },
+            "created_at": created_at,
+            "payload": payload,
+        }))
+        .unwrap()
+    }
+
+    fn commit(sha: &str) -> CommitDetail {
+        serde_json::from_value(json!({ "sha": sha, "commit": { "message": "Fix it" } })).unwrap()
+    }
+
+    fn push(id: &str, repo: &str, shas: &[&str]) -> EventDetail {
+        let commits: Vec<Value> = shas.iter().map(|sha| json!({ "sha": sha, "message": "Fix it" })).collect();
+        let payload = json!({ "ref": "refs/heads/main", "size": shas.len(), "commits": commits });
+        let event = event(id, "PushEvent", repo, "2024-02-10T10:00:00Z", payload);
+        (event, shas.iter().map(|sha| commit(sha)).collect(), None)
+    }
+
+    /// A push that only knows its commit count, like GraphQL's.
+    fn count_only(id: &str, repo: &str, size: u64) -> EventDetail {
+        (event(id, "PushEvent", repo, "2024-02-10T08:00:00Z", json!({ "size": size })), Vec::new(), None)
+    }
+
+    fn changed(sha: &str, patch: &str) -> CommitDetail {
+        serde_json::from_value(json!({
+            "sha": sha,
+            "commit": { "message": "Fix it" },
+            "files": [{ "filename": "src/main.rs", "additions": 1, "deletions": 1, "patch": patch }],
+        }))
+        .unwrap()
+    }
+
+    /// A push to `branch` at `time` (`HH:MM`) holding `commits`, as
+    /// `dedupe_commits` gets it.
+    fn push_to(id: &str, repo: &str, branch: &str, time: &str, commits: Vec<CommitDetail>) -> (String, EventDetail) {
+        let payload = json!({ "ref": format!("refs/heads/{}", branch), "size": commits.len() });
+        let event = event(id, "PushEvent", repo, &format!("{}T{}:00Z", DAY, time), payload);
+        (DAY.to_string(), (event, commits, None))
+    }
+
+    /// A push's event ID, commit SHAs and each commit's `also_pushed_to`.
+    type PushCommits<'a> = (&'a str, Vec<&'a str>, Vec<Vec<String>>);
+
+    fn commits_by_push(processed: &[(String, EventDetail)]) -> Vec<PushCommits<'_>> {
+        processed.iter()
+            .map(|(_, (event, commits, _))| (
+                event.id.as_str(),
+                commits.iter().map(|commit| commit.sha.as_str()).collect(),
+                commits.iter().map(|commit| commit.also_pushed_to.clone()).collect(),
+            ))
+            .collect()
+    }
+
+    fn pull_request(id: &str, repo: &str, number: i64) -> EventDetail {
+        let payload = json!({ "action": "opened", "number": number });
+        (event(id, "PullRequestEvent", repo, "2024-02-10T09:00:00Z", payload), Vec::new(), None)
+    }
+
+    fn issue(id: &str, repo: &str, number: i64) -> EventDetail {
+        let payload = json!({ "action": "opened", "issue": { "number": number } });
+        (event(id, "IssuesEvent", repo, "2024-02-10T09:00:00Z", payload), Vec::new(), None)
+    }
+
+    fn day(details: Vec<EventDetail>) -> DailySummaries {
+        HashMap::from([(DAY.to_string(), details)])
+    }
+
+    fn merged(sources: Vec<Vec<EventDetail>>) -> DailySummaries {
+        let mut daily_summaries = DailySummaries::new();
+        for details in sources {
+            merge_summaries(&mut daily_summaries, day(details));
+        }
+        daily_summaries
+    }
+
+    /// The IDs of the events on `DAY`, sorted.
+    fn ids(daily_summaries: &DailySummaries) -> Vec<&str> {
+        let mut ids: Vec<&str> = daily_summaries[DAY].iter().map(|detail| detail.0.id.as_str()).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn count_only_push_gives_way_to_commits_from_a_later_source() {
+        let daily_summaries = merged(vec![
+            vec![count_only("graphql", "octo/repo", 2)],
+            vec![push("events", "octo/repo", &["a", "b"])],
+        ]);
+        assert_eq!(ids(&daily_summaries), ["events"]);
+    }
+
+    #[test]
+    fn count_only_push_is_skipped_after_commits_from_an_earlier_source() {
+        let daily_summaries = merged(vec![
+            vec![push("events", "octo/repo", &["a", "b"])],
+            vec![count_only("graphql", "octo/repo", 2)],
+        ]);
+        assert_eq!(ids(&daily_summaries), ["events"]);
+    }
+
+    #[test]
+    fn count_only_push_to_another_repository_is_kept() {
+        let daily_summaries = merged(vec![
+            vec![push("events", "octo/repo", &["a"])],
+            vec![count_only("graphql", "octo/other", 1)],
+        ]);
+        assert_eq!(ids(&daily_summaries), ["events", "graphql"]);
+    }
+
+    #[test]
+    fn commits_already_present_are_dropped_from_later_pushes() {
+        let daily_summaries = merged(vec![
+            vec![push("events", "octo/repo", &["a", "b"])],
+            vec![push("search", "octo/repo", &["b", "c"]), push("search-dup", "octo/repo", &["a"])],
+        ]);
+        assert_eq!(ids(&daily_summaries), ["events", "search"]);
+        let search = daily_summaries[DAY].iter().find(|detail| detail.0.id == "search").unwrap();
+        assert_eq!(search.1.iter().map(|commit| commit.sha.as_str()).collect::<Vec<_>>(), ["c"]);
+        assert!(matches!(&search.0.payload, Event::Push(push) if push.size == 1));
+    }
+
+    #[test]
+    fn pull_requests_and_issues_are_matched_by_type_repository_and_number() {
+        let daily_summaries = merged(vec![
+            vec![pull_request("events-pr", "octo/repo", 5)],
+            vec![
+                pull_request("search-pr", "octo/repo", 5),
+                pull_request("search-pr-other-repo", "octo/other", 5),
+                issue("search-issue", "octo/repo", 5),
+            ],
+        ]);
+        assert_eq!(ids(&daily_summaries), ["events-pr", "search-issue", "search-pr-other-repo"]);
+    }
+
+    #[test]
+    fn one_source_can_report_the_same_pull_request_twice() {
+        let daily_summaries = merged(vec![
+            vec![pull_request("opened", "octo/repo", 5), pull_request("closed", "octo/repo", 5)],
+        ]);
+        assert_eq!(ids(&daily_summaries), ["closed", "opened"]);
+    }
+
+    #[test]
+    fn patch_id_ignores_line_numbers_and_whitespace() {
+        let original = changed("a", "@@ -1,2 +1,2 @@\n-let x = 1;\n+let x = 2;");
+        let rebased = changed("b", "@@ -10,2 +12,2 @@\n-let x=1;\n+let  x = 2;");
+        assert!(patch_id(&original).is_some());
+        assert_eq!(patch_id(&original), patch_id(&rebased));
+        assert_ne!(patch_id(&original), patch_id(&changed("c", "@@ -1 +1 @@\n-let x = 1;\n+let x = 3;")));
+    }
+
+    #[test]
+    fn patch_id_needs_every_diff_and_a_changed_line() {
+        assert_eq!(patch_id(&commit("a")), None);
+        assert_eq!(patch_id(&changed("a", "@@ -1 +1 @@")), None);
+        let mut missing = changed("a", "@@ -1 +1 @@\n-a\n+b");
+        missing.files.push(serde_json::from_value(json!({ "filename": "logo.png" })).unwrap());
+        assert_eq!(patch_id(&missing), None);
+    }
+
+    #[test]
+    fn dedupe_keeps_a_commit_in_its_earliest_push_and_notes_other_branches() {
+        let processed = dedupe_commits(vec![
+            push_to("release", "octo/repo", "release", "12:00", vec![commit("a")]),
+            push_to("feature", "octo/repo", "feature", "10:00", vec![commit("a"), commit("b")]),
+            push_to("main", "octo/repo", "main", "11:00", vec![commit("b"), commit("c")]),
+        ]);
+        assert_eq!(commits_by_push(&processed), [
+            ("feature", vec!["a", "b"], vec![vec!["release".to_string()], vec!["main".to_string()]]),
+            ("main", vec!["c"], vec![vec![]]),
+        ]);
+        assert!(matches!(&processed[1].1.0.payload, Event::Push(push) if push.size == 1));
+    }
+
+    #[test]
+    fn dedupe_matches_shas_across_repositories() {
+        let processed = dedupe_commits(vec![
+            push_to("fork", "octo/fork", "fix", "10:00", vec![commit("a")]),
+            push_to("upstream", "upstream/repo", "main", "11:00", vec![commit("a")]),
+        ]);
+        assert_eq!(commits_by_push(&processed), [("fork", vec!["a"], vec![vec!["upstream/repo:main".to_string()]])]);
+    }
+
+    #[test]
+    fn dedupe_matches_patch_ids_only_within_a_repository() {
+        let patch = "@@ -1 +1 @@\n-old\n+new";
+        let processed = dedupe_commits(vec![
+            push_to("first", "octo/repo", "main", "10:00", vec![changed("a", patch)]),
+            push_to("rebased", "octo/repo", "rebased", "11:00", vec![changed("b", patch)]),
+            push_to("bot", "octo/other", "main", "12:00", vec![changed("c", patch)]),
+        ]);
+        assert_eq!(commits_by_push(&processed), [
+            ("first", vec!["a"], vec![vec!["rebased".to_string()]]),
+            ("bot", vec!["c"], vec![vec![]]),
+        ]);
+    }
+}
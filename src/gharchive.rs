@@ -0,0 +1,55 @@
+use flate2::read::MultiGzDecoder;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use crate::api::GitHubEvent;
+
+/// GH Archive lines are the same events the API returns, plus the actor.
+#[derive(Deserialize)]
+struct ArchivedEvent {
+    actor: Actor,
+    #[serde(flatten)]
+    event: GitHubEvent,
+}
+
+#[derive(Deserialize)]
+struct Actor {
+    login: String,
+}
+
+/// Cheap check before parsing: most lines in an hourly dump are about
+/// other users and don't mention the login anywhere.
+fn mentions(line: &str, login: &str) -> bool {
+    line.as_bytes()
+        .windows(login.len())
+        .any(|window| window.eq_ignore_ascii_case(login.as_bytes()))
+}
+
+/// Streams a GH Archive `.json.gz` dump (one event per line) and returns
+/// the events whose actor is `username`.
+pub fn read_events(path: &Path, username: &str) -> Result<Vec<GitHubEvent>, Box<dyn Error>> {
+    let reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    let mut events = Vec::new();
+    let mut skipped = 0;
+    for line in reader.lines() {
+        let line = line?;
+        if username.is_empty() || !mentions(&line, username) {
+            continue;
+        }
+        match serde_json::from_str::<ArchivedEvent>(&line) {
+            Ok(archived) if archived.actor.login.eq_ignore_ascii_case(username) => events.push(archived.event),
+            Ok(_) => {}
+            Err(e) => {
+                log::debug!("Skipping unparseable line in {}: {}", path.display(), e);
+                skipped += 1;
+            }
+        }
+    }
+    if skipped > 0 {
+        log::warn!("Skipped {} unparseable events in {}", skipped, path.display());
+    }
+    log::debug!("Found {} events by {} in {}", events.len(), username, path.display());
+    Ok(events)
+}
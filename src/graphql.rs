@@ -0,0 +1,408 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use crate::api::{post_json, ApiClient, GitHubEvent, PullRequestDetail, Repository};
+use crate::events::{DailySummaries, EventDetail};
+use crate::payload::{
+    Event, IssueSummary, IssuesPayload, PullRequestPayload, PullRequestReviewPayload, PullRequestSummary, PushPayload,
+    Review,
+};
+
+/// One query covers at most this many days. Keeping windows to about a
+/// month means a repository never has more than the 100 days of commit
+/// contributions a single page returns.
+const WINDOW_DAYS: i64 = 31;
+
+/// Repositories `commitContributionsByRepository` returns at most, with no
+/// way to page past them.
+const MAX_REPOSITORIES: usize = 100;
+
+/// Connections that are done paging are left out with `@include`, so later
+/// pages only fetch what's still missing. Commits aren't paged and only
+/// come with the first page.
+const CONTRIBUTIONS_QUERY: &str = r#"
+query(
+  $login: String!, $from: DateTime!, $to: DateTime!, $commits: Boolean!,
+  $pullRequests: Boolean!, $pullRequestsAfter: String,
+  $issues: Boolean!, $issuesAfter: String,
+  $reviews: Boolean!, $reviewsAfter: String
+) {
+  user(login: $login) {
+    contributionsCollection(from: $from, to: $to) {
+      commitContributionsByRepository(maxRepositories: 100) @include(if: $commits) {
+        repository { nameWithOwner }
+        contributions(first: 100) { nodes { occurredAt commitCount } }
+      }
+      pullRequestContributions(first: 100, after: $pullRequestsAfter) @include(if: $pullRequests) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          occurredAt
+          pullRequest { number title body state merged url repository { nameWithOwner } }
+        }
+      }
+      issueContributions(first: 100, after: $issuesAfter) @include(if: $issues) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          occurredAt
+          issue { number title state url repository { nameWithOwner } }
+        }
+      }
+      pullRequestReviewContributions(first: 100, after: $reviewsAfter) @include(if: $reviews) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          occurredAt
+          pullRequestReview { databaseId state url }
+          pullRequest { number title url repository { nameWithOwner } }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Connections not asked for in a page are missing from the response.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ContributionsCollection {
+    #[serde(default)]
+    commit_contributions_by_repository: Vec<RepositoryCommits>,
+    #[serde(default)]
+    pull_request_contributions: Option<Connection<PullRequestContribution>>,
+    #[serde(default)]
+    issue_contributions: Option<Connection<IssueContribution>>,
+    #[serde(default)]
+    pull_request_review_contributions: Option<Connection<ReviewContribution>>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Connection<T> {
+    #[serde(default)]
+    page_info: Option<PageInfo>,
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    #[serde(default)]
+    end_cursor: Option<String>,
+}
+
+/// Where a paged connection continues.
+#[derive(Debug)]
+enum Cursor {
+    Start,
+    After(String),
+    Done,
+}
+
+impl Cursor {
+    /// The cursor for the page after `connection`, which is `Done` when
+    /// there is none or the connection wasn't fetched.
+    fn next<T>(connection: Option<&Connection<T>>) -> Cursor {
+        match connection.and_then(|connection| connection.page_info.as_ref()) {
+            Some(PageInfo { has_next_page: true, end_cursor: Some(cursor) }) => Cursor::After(cursor.clone()),
+            _ => Cursor::Done,
+        }
+    }
+
+    fn wanted(&self) -> bool {
+        !matches!(self, Cursor::Done)
+    }
+
+    fn after(&self) -> Option<&str> {
+        match self {
+            Cursor::After(cursor) => Some(cursor),
+            _ => None,
+        }
+    }
+}
+
+/// What the next page of a window asks for.
+struct PageRequest {
+    commits: bool,
+    pull_requests: Cursor,
+    issues: Cursor,
+    reviews: Cursor,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct NamedRepository {
+    name_with_owner: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct RepositoryCommits {
+    repository: NamedRepository,
+    contributions: Connection<CommitContribution>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CommitContribution {
+    occurred_at: String,
+    commit_count: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PullRequestContribution {
+    occurred_at: String,
+    pull_request: GraphQlPullRequest,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlPullRequest {
+    number: i32,
+    title: String,
+    body: Option<String>,
+    state: String,
+    merged: bool,
+    url: String,
+    repository: NamedRepository,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct IssueContribution {
+    occurred_at: String,
+    issue: GraphQlIssue,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlIssue {
+    number: i64,
+    title: String,
+    state: String,
+    url: String,
+    repository: NamedRepository,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ReviewContribution {
+    occurred_at: String,
+    pull_request_review: GraphQlReview,
+    pull_request: GraphQlReviewedPullRequest,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GraphQlReview {
+    database_id: Option<u64>,
+    state: String,
+    url: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQlReviewedPullRequest {
+    number: i64,
+    title: String,
+    url: String,
+    repository: NamedRepository,
+}
+
+/// The GraphQL endpoint that goes with a REST base URL: `/graphql` on
+/// github.com, `/api/graphql` on Enterprise Server (`/api/v3`).
+fn graphql_url(api: &ApiClient) -> String {
+    match api.base_url.strip_suffix("/api/v3") {
+        Some(host) => format!("{}/api/graphql", host),
+        None => api.url("/graphql"),
+    }
+}
+
+fn fetch_page(
+    api: &ApiClient,
+    username: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    page: &PageRequest,
+) -> Result<ContributionsCollection, Box<dyn Error>> {
+    let body = json!({
+        "query": CONTRIBUTIONS_QUERY,
+        "variables": {
+            "login": username,
+            "from": from.to_rfc3339(),
+            "to": to.to_rfc3339(),
+            "commits": page.commits,
+            "pullRequests": page.pull_requests.wanted(),
+            "pullRequestsAfter": page.pull_requests.after(),
+            "issues": page.issues.wanted(),
+            "issuesAfter": page.issues.after(),
+            "reviews": page.reviews.wanted(),
+            "reviewsAfter": page.reviews.after(),
+        },
+    });
+    log::debug!("Fetching GraphQL contributions for {} from {} to {} ({:?}, {:?}, {:?})",
+        username, from, to, page.pull_requests, page.issues, page.reviews);
+    let response = post_json(api, &graphql_url(api), &body)?;
+    if let Some(errors) = response.get("errors") {
+        log::error!("GraphQL errors: {}", errors);
+        return Err("GraphQL query failed".into());
+    }
+    let collection = response
+        .pointer("/data/user/contributionsCollection")
+        .cloned()
+        .ok_or_else(|| format!("No contributions returned for user {}", username))?;
+    Ok(serde_json::from_value(collection)?)
+}
+
+/// Every contribution in one window, paging through pull requests, issues
+/// and reviews until each is complete.
+fn fetch_window(api: &ApiClient, username: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<EventDetail>, Box<dyn Error>> {
+    let mut details = Vec::new();
+    let mut page = PageRequest { commits: true, pull_requests: Cursor::Start, issues: Cursor::Start, reviews: Cursor::Start };
+    loop {
+        let collection = fetch_page(api, username, from, to, &page)?;
+        if collection.commit_contributions_by_repository.len() >= MAX_REPOSITORIES {
+            log::warn!("Commits in {} or more repositories in the window starting {}, some may be missing.",
+                MAX_REPOSITORIES, from.format("%Y-%m-%d"));
+        }
+        page = PageRequest {
+            commits: false,
+            pull_requests: Cursor::next(collection.pull_request_contributions.as_ref()),
+            issues: Cursor::next(collection.issue_contributions.as_ref()),
+            reviews: Cursor::next(collection.pull_request_review_contributions.as_ref()),
+        };
+        details.extend(to_event_details(collection));
+        if !(page.pull_requests.wanted() || page.issues.wanted() || page.reviews.wanted()) {
+            return Ok(details);
+        }
+    }
+}
+
+/// Builds an event shaped like the REST events feed, so contributions from
+/// GraphQL render the same way as everything else.
+fn synthetic_event(id: String, repo: &str, created_at: &str, payload: Event) -> GitHubEvent {
+    GitHubEvent {
+        id,
+        created_at: created_at.to_string(),
+        repo: Repository { name: repo.to_string() },
+        payload,
+        raw_payload: None,
+    }
+}
+
+fn to_event_details(collection: ContributionsCollection) -> Vec<EventDetail> {
+    let mut details: Vec<EventDetail> = Vec::new();
+
+    for repo_commits in collection.commit_contributions_by_repository {
+        let repo = repo_commits.repository.name_with_owner;
+        for contribution in repo_commits.contributions.nodes {
+            let event = synthetic_event(
+                format!("graphql-commits:{}:{}", repo, contribution.occurred_at),
+                &repo,
+                &contribution.occurred_at,
+                Event::Push(PushPayload { size: contribution.commit_count, ..Default::default() }),
+            );
+            details.push((event, Vec::new(), None));
+        }
+    }
+
+    for contribution in collection.pull_request_contributions.into_iter().flat_map(|connection| connection.nodes) {
+        let pr = contribution.pull_request;
+        let event = synthetic_event(
+            format!("graphql-pr:{}:{}", pr.repository.name_with_owner, pr.number),
+            &pr.repository.name_with_owner,
+            &contribution.occurred_at,
+            Event::PullRequest(PullRequestPayload {
+                action: "opened".to_string(),
+                number: pr.number as i64,
+                pull_request: None,
+            }),
+        );
+        let detail = PullRequestDetail {
+            number: pr.number,
+            title: pr.title,
+            body: pr.body,
+            // REST reports merged PRs as "closed" with `merged: true`.
+            state: if pr.state == "MERGED" { "closed".to_string() } else { pr.state.to_lowercase() },
+            merged: pr.merged,
+            html_url: pr.url,
+        };
+        details.push((event, Vec::new(), Some(detail)));
+    }
+
+    for contribution in collection.issue_contributions.into_iter().flat_map(|connection| connection.nodes) {
+        let issue = contribution.issue;
+        let event = synthetic_event(
+            format!("graphql-issue:{}:{}", issue.repository.name_with_owner, issue.number),
+            &issue.repository.name_with_owner,
+            &contribution.occurred_at,
+            Event::Issues(IssuesPayload {
+                action: "opened".to_string(),
+                issue: IssueSummary {
+                    number: issue.number,
+                    title: issue.title,
+                    state: issue.state.to_lowercase(),
+                    html_url: issue.url,
+                    pull_request: None,
+                },
+            }),
+        );
+        details.push((event, Vec::new(), None));
+    }
+
+    for contribution in collection.pull_request_review_contributions.into_iter().flat_map(|connection| connection.nodes) {
+        let pr = contribution.pull_request;
+        let review = contribution.pull_request_review;
+        let event = synthetic_event(
+            format!("graphql-review:{}", review.url),
+            &pr.repository.name_with_owner,
+            &contribution.occurred_at,
+            Event::PullRequestReview(PullRequestReviewPayload {
+                action: "created".to_string(),
+                review: Review { id: review.database_id, state: review.state.to_lowercase(), html_url: review.url },
+                pull_request: PullRequestSummary { number: pr.number, title: pr.title, html_url: pr.url },
+            }),
+        );
+        details.push((event, Vec::new(), None));
+    }
+
+    details
+}
+
+/// Fetches contributions from GraphQL's `contributionsCollection`, which
+/// unlike the events feed can answer for any date range. Commits are only
+/// available as daily counts per repository, not individual messages.
+pub fn fetch_contributions(
+    api: &ApiClient,
+    username: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    tz: Tz,
+) -> Result<DailySummaries, Box<dyn Error>> {
+    if api.token.is_empty() {
+        return Err("The GraphQL source needs a GITHUB_TOKEN".into());
+    }
+
+    let mut daily_summaries: DailySummaries = HashMap::new();
+    let mut from = start_date;
+    while from <= end_date {
+        let to = (from + Duration::days(WINDOW_DAYS) - Duration::seconds(1)).min(end_date);
+        for detail in fetch_window(api, username, from, to)? {
+            let event_time = match DateTime::parse_from_rfc3339(&detail.0.created_at) {
+                Ok(time) => time,
+                Err(e) => {
+                    log::warn!("Failed to parse contribution time {}: {}", detail.0.created_at, e);
+                    continue;
+                }
+            };
+            let day_key = event_time.with_timezone(&tz).format("%Y-%m-%d").to_string();
+            daily_summaries.entry(day_key).or_default().push(detail);
+        }
+        from = to + Duration::seconds(1);
+    }
+
+    log::debug!("GraphQL contributions by day: {:?}", daily_summaries);
+    if daily_summaries.is_empty() {
+        log::warn!("No contributions found in the specified range.");
+    }
+    Ok(daily_summaries)
+}
@@ -0,0 +1,306 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write;
+use crate::payload::Event;
+use crate::report::{Report, ReportEvent};
+use crate::summary::{preview, review_outcome, COMMENT_PREVIEW_CHARS};
+
+/// Everything is inlined so the page works offline, e.g. as an email
+/// attachment.
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; color: #1f2328; max-width: 960px; margin: 2em auto; padding: 0 1em; line-height: 1.5; }
+h1 { margin-bottom: 0; }
+.range { color: #59636e; margin-top: 0.25em; }
+.tiles { display: flex; flex-wrap: wrap; gap: 1em; margin: 1.5em 0; }
+.tile { border: 1px solid #d1d9e0; border-radius: 6px; padding: 0.5em 1em; min-width: 8em; }
+.tile strong { display: block; font-size: 1.5em; }
+.tile span { color: #59636e; font-size: 0.9em; }
+.calendar { overflow-x: auto; }
+.calendar text, .chart text { font-size: 10px; fill: #59636e; }
+.charts { display: flex; flex-wrap: wrap; gap: 2em; }
+.chart text.label { fill: #1f2328; font-size: 12px; }
+.note { color: #59636e; font-size: 0.9em; }
+details { border-bottom: 1px solid #d1d9e0; padding: 0.5em 0; }
+summary { cursor: pointer; }
+summary .stats { color: #59636e; }
+ul.events { padding-left: 1.25em; }
+ul.events > li { margin: 0.5em 0; }
+.type { font-weight: 600; }
+.time { color: #59636e; }
+.body { white-space: pre-wrap; color: #59636e; }
+blockquote { margin: 0.25em 0; padding-left: 0.75em; border-left: 3px solid #d1d9e0; color: #59636e; }
+code { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 0.9em; }
+a { color: #0969da; text-decoration: none; }
+a:hover { text-decoration: underline; }
+"#;
+
+/// GitHub's calendar colors, from no activity to the busiest days.
+const LEVEL_COLORS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+
+/// Calendar cell size and the distance between cells, in pixels.
+const CELL: i64 = 11;
+const STEP: i64 = 13;
+
+/// Bars shown per chart; the rest are counted in a note below it.
+const CHART_ROWS: usize = 10;
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// `html` linked to `url`, or left as it is when there's no URL.
+fn link(url: &str, html: &str) -> String {
+    if url.is_empty() {
+        html.to_string()
+    } else {
+        format!(r#"<a href="{}">{}</a>"#, escape(url), html)
+    }
+}
+
+/// The summary numbers at the top of the page.
+fn write_tiles(out: &mut String, report: &Report) -> Result<(), Box<dyn Error>> {
+    let totals = &report.totals;
+    let mut tiles = vec![
+        (totals.events.to_string(), "events"),
+        (totals.active_days.to_string(), "active days"),
+        (totals.commits.to_string(), "commits"),
+    ];
+    if let Some(lines) = &totals.lines {
+        tiles.push((format!("+{}/−{}", lines.additions, lines.deletions), "lines changed"));
+    }
+    let reviews: usize = totals.reviews.values().sum();
+    if reviews > 0 {
+        tiles.push((reviews.to_string(), "reviews given"));
+    }
+    writeln!(out, r#"<div class="tiles">"#)?;
+    for (value, label) in tiles {
+        writeln!(out, r#"<div class="tile"><strong>{}</strong><span>{}</span></div>"#, value, label)?;
+    }
+    writeln!(out, "</div>")?;
+    Ok(())
+}
+
+/// A GitHub-style calendar of the range: one column per week starting on
+/// Monday, shaded by the number of events. Active days link to their
+/// section below.
+fn write_heatmap(out: &mut String, report: &Report) -> Result<(), Box<dyn Error>> {
+    let start = NaiveDate::parse_from_str(&report.range.start, "%Y-%m-%d")?;
+    let end = NaiveDate::parse_from_str(&report.range.end, "%Y-%m-%d")?;
+    let counts: HashMap<&str, usize> = report.days.iter().map(|day| (day.date.as_str(), day.totals.events)).collect();
+    let busiest = counts.values().copied().max().unwrap_or(0);
+
+    let first_monday = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+    let weeks = (end - first_monday).num_days() / 7 + 1;
+    let (left, top) = (30, 15);
+    writeln!(out, r#"<div class="calendar"><svg width="{}" height="{}" role="img" aria-label="Events per day">"#,
+        left + weeks * STEP, top + 7 * STEP)?;
+    for (row, name) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+        writeln!(out, r#"<text x="0" y="{}">{}</text>"#, top + row * STEP + CELL - 1, name)?;
+    }
+    for date in start.iter_days().take_while(|date| *date <= end) {
+        let x = left + (date - first_monday).num_days() / 7 * STEP;
+        let y = top + date.weekday().num_days_from_monday() as i64 * STEP;
+        // Label a month where it starts, and the first one when at least
+        // three weeks of it are in the range.
+        if date.day() == 1 || (date == start && (start + Duration::days(21)).month() == start.month()) {
+            writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, x, top - 4, date.format("%b"))?;
+        }
+        let key = date.format("%Y-%m-%d").to_string();
+        let count = counts.get(key.as_str()).copied().unwrap_or(0);
+        let level = if count == 0 { 0 } else { (count * 4).div_ceil(busiest).clamp(1, 4) };
+        let cell = format!(r#"<rect x="{}" y="{}" width="{}" height="{}" rx="2" fill="{}"><title>{}: {} event(s)</title></rect>"#,
+            x, y, CELL, CELL, LEVEL_COLORS[level], key, count);
+        if count > 0 {
+            writeln!(out, r##"<a href="#day-{}">{}</a>"##, key, cell)?;
+        } else {
+            writeln!(out, "{}", cell)?;
+        }
+    }
+    writeln!(out, "</svg></div>")?;
+
+    write!(out, r#"<p class="note">Less "#)?;
+    for color in LEVEL_COLORS {
+        write!(out, r#"<svg width="{0}" height="{0}"><rect width="{0}" height="{0}" rx="2" fill="{1}"/></svg> "#, CELL, color)?;
+    }
+    writeln!(out, "More</p>")?;
+    Ok(())
+}
+
+/// A horizontal bar chart of counts, largest first.
+fn write_bar_chart(out: &mut String, title: &str, mut rows: Vec<(String, usize)>) -> Result<(), Box<dyn Error>> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let hidden = rows.len().saturating_sub(CHART_ROWS);
+    rows.truncate(CHART_ROWS);
+    let largest = rows[0].1.max(1);
+    let (label_width, bar_width, row_height) = (200, 240, 22);
+
+    writeln!(out, r#"<div class="chart"><h3>{}</h3>"#, escape(title))?;
+    writeln!(out, r#"<svg width="{}" height="{}">"#, label_width + bar_width + 40, rows.len() * row_height)?;
+    for (i, (label, count)) in rows.iter().enumerate() {
+        let y = i * row_height;
+        let width = (count * bar_width / largest).max(1);
+        writeln!(out, r#"<text class="label" x="{}" y="{}" text-anchor="end">{}<title>{}</title></text>"#,
+            label_width - 8, y + 15, escape(&preview(label, 28)), escape(label))?;
+        writeln!(out, r##"<rect x="{}" y="{}" width="{}" height="14" rx="2" fill="#40c463"/>"##, label_width, y + 4, width)?;
+        writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, label_width + width + 6, y + 15, count)?;
+    }
+    writeln!(out, "</svg>")?;
+    if hidden > 0 {
+        writeln!(out, r#"<p class="note">and {} more</p>"#, hidden)?;
+    }
+    writeln!(out, "</div>")?;
+    Ok(())
+}
+
+fn write_charts(out: &mut String, report: &Report) -> Result<(), Box<dyn Error>> {
+    let event_types = report.totals.event_types.iter().map(|(name, count)| (name.clone(), *count)).collect();
+    let mut repos: HashMap<&str, usize> = HashMap::new();
+    for event in report.days.iter().flat_map(|day| &day.events) {
+        *repos.entry(&event.repo).or_insert(0) += 1;
+    }
+    let repos = repos.into_iter().map(|(repo, count)| (repo.to_string(), count)).collect();
+
+    writeln!(out, r#"<div class="charts">"#)?;
+    write_bar_chart(out, "Event types", event_types)?;
+    write_bar_chart(out, "Repositories", repos)?;
+    writeln!(out, "</div>")?;
+    Ok(())
+}
+
+/// What an event did, as an HTML fragment, like the Markdown report's
+/// lines under each event.
+fn event_details(event: &ReportEvent) -> String {
+    let repo = format!("<code>{}</code>", escape(&event.repo));
+    match &event.payload {
+        Event::Push(_) if !event.commits.is_empty() => {
+            let mut commits = String::from("<ul>");
+            for commit in &event.commits {
+                let short_sha: String = commit.sha.chars().take(7).collect();
+                let sha = format!(r#"<code title="{}">{}</code>"#, escape(&commit.sha), escape(&short_sha));
+                let (first_line, body) = commit.message.split_once('\n').unwrap_or((&commit.message, ""));
+                commits.push_str(&format!("<li>{} {}", link(commit.url.as_deref().unwrap_or_default(), &sha), escape(first_line)));
+                if let Some(author) = &commit.other_author {
+                    commits.push_str(&format!(" <span class=\"time\">by {}</span>", escape(author)));
+                }
+                if let Some(stats) = &commit.stats {
+                    commits.push_str(&format!(" <span class=\"time\">+{}/−{}</span>", stats.additions, stats.deletions));
+                }
+                if !body.trim().is_empty() {
+                    commits.push_str(&format!(r#"<div class="body">{}</div>"#, escape(body.trim())));
+                }
+                if !commit.also_pushed_to.is_empty() {
+                    let branches: Vec<String> = commit.also_pushed_to.iter()
+                        .map(|branch| format!("<code>{}</code>", escape(branch)))
+                        .collect();
+                    commits.push_str(&format!(r#"<div class="note">Also pushed to {}</div>"#, branches.join(", ")));
+                }
+                commits.push_str("</li>");
+            }
+            commits.push_str("</ul>");
+            commits
+        }
+        Event::Push(push) => format!("{} commit(s)", push.size),
+        Event::PullRequest(pull_request) => match (&event.pull_request, &pull_request.pull_request) {
+            (Some(pr), _) => format!("PR {} {} ({}, {}{})",
+                link(&pr.url, &format!("#{}", pr.number)), escape(&pr.title), escape(&pull_request.action),
+                escape(&pr.state), if pr.merged { ", merged" } else { "" }),
+            (None, Some(pr)) => format!("PR {} {} ({})",
+                link(&pr.html_url, &format!("#{}", pr.number)), escape(&pr.title), escape(&pull_request.action)),
+            (None, None) => format!("PR #{} ({})", pull_request.number, escape(&pull_request.action)),
+        },
+        Event::Create(created) => format!("Created {} <code>{}</code>",
+            escape(&created.ref_type), escape(created.git_ref.as_deref().unwrap_or(&event.repo))),
+        Event::Delete(deleted) => format!("Deleted {} <code>{}</code>",
+            escape(&deleted.ref_type), escape(deleted.git_ref.as_deref().unwrap_or("none"))),
+        Event::Issues(issues) => format!("Issue {} {} ({}, {})",
+            link(&issues.issue.html_url, &format!("#{}", issues.issue.number)), escape(&issues.issue.title),
+            escape(&issues.action), escape(&issues.issue.state)),
+        Event::IssueComment(comment) => {
+            let kind = if comment.issue.pull_request.is_some() { "PR" } else { "issue" };
+            format!("Commented on {} {} {}<blockquote>{}</blockquote>",
+                kind, link(&comment.comment.html_url, &format!("#{}", comment.issue.number)), escape(&comment.issue.title),
+                escape(&preview(&comment.comment.body, COMMENT_PREVIEW_CHARS)))
+        }
+        Event::PullRequestReview(review) => format!("Reviewed PR {} {} ({}, {} inline comment(s))",
+            link(&review.review.html_url, &format!("#{}", review.pull_request.number)), escape(&review.pull_request.title),
+            escape(&review_outcome(&review.review.state)), event.review_comments.unwrap_or(0)),
+        Event::PullRequestReviewComment(comment) => format!("Review comment on PR {} {}<blockquote><code>{}</code>: {}</blockquote>",
+            link(&comment.comment.html_url, &format!("#{}", comment.pull_request.number)), escape(&comment.pull_request.title),
+            escape(&comment.comment.path), escape(&preview(&comment.comment.body, COMMENT_PREVIEW_CHARS))),
+        Event::Release(release) => {
+            let name = release.release.name.as_deref().filter(|name| !name.is_empty()).unwrap_or(&release.release.tag_name);
+            format!("Release {} {} ({})",
+                link(&release.release.html_url, &format!("<code>{}</code>", escape(&release.release.tag_name))),
+                escape(name), escape(&release.action))
+        }
+        Event::Fork(fork) => format!("Forked to {}",
+            link(&fork.forkee.html_url, &format!("<code>{}</code>", escape(&fork.forkee.full_name)))),
+        Event::Watch(_) => format!("Starred {}", repo),
+        Event::Member(member) => format!("Collaborator {} {}",
+            link(&member.member.html_url, &escape(&member.member.login)), escape(&member.action)),
+        Event::Public => format!("Made {} public", repo),
+        Event::Gollum(gollum) => gollum.pages.iter()
+            .map(|page| format!("{} wiki page {}",
+                if page.action == "created" { "Created" } else { "Edited" }, link(&page.html_url, &escape(&page.title))))
+            .collect::<Vec<String>>()
+            .join("<br>"),
+        Event::Unknown { .. } => String::new(),
+    }
+}
+
+/// One collapsible section per active day.
+fn write_days(out: &mut String, report: &Report) -> Result<(), Box<dyn Error>> {
+    writeln!(out, "<h2>Days</h2>")?;
+    for day in &report.days {
+        let (Some(first), Some(last)) = (day.events.first(), day.events.last()) else { continue };
+        let lines = day.totals.lines.as_ref()
+            .map(|lines| format!(", +{}/−{}", lines.additions, lines.deletions))
+            .unwrap_or_default();
+        writeln!(out, r#"<details id="day-{}">"#, day.date)?;
+        writeln!(out, r#"<summary><strong>{}</strong> <span class="stats">{} event(s), {}–{}{}</span></summary>"#,
+            day.date, day.totals.events, first.time.format("%H:%M"), last.time.format("%H:%M %Z"), lines)?;
+        writeln!(out, r#"<ul class="events">"#)?;
+        for event in &day.events {
+            writeln!(out, r#"<li><span class="time">{}</span> <span class="type">{}</span> <code>{}</code><br>{}</li>"#,
+                event.time.format("%H:%M"), escape(&event.event_type), escape(&event.repo), event_details(event))?;
+        }
+        writeln!(out, "</ul>\n</details>")?;
+    }
+    if report.days.is_empty() {
+        writeln!(out, r#"<p class="note">No contributions in this range.</p>"#)?;
+    }
+    Ok(())
+}
+
+/// The report as a single HTML page with no external resources.
+pub fn render(report: &Report) -> Result<String, Box<dyn Error>> {
+    let range = format!("{} to {} ({})", report.range.start, report.range.end, report.range.timezone);
+    let mut out = String::new();
+    writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#)?;
+    writeln!(out, "<title>GitHub Contributions Summary, {}</title>", escape(&range))?;
+    writeln!(out, "<style>{}</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<h1>GitHub Contributions Summary</h1>")?;
+    writeln!(out, r#"<p class="range">{}</p>"#, escape(&range))?;
+    write_tiles(&mut out, report)?;
+    write_heatmap(&mut out, report)?;
+    write_charts(&mut out, report)?;
+    write_days(&mut out, report)?;
+    writeln!(out, "</body>\n</html>")?;
+    Ok(out)
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+/// Extensions (and a few well-known extension-less file names) and the
+/// language they're counted as. The config file's `[languages]` table
+/// adds to and overrides this.
+const BUILTIN_LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("go", "Go"),
+    ("py", "Python"),
+    ("pyi", "Python"),
+    ("rb", "Ruby"),
+    ("js", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("ts", "TypeScript"),
+    ("mts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("java", "Java"),
+    ("kt", "Kotlin"),
+    ("kts", "Kotlin"),
+    ("scala", "Scala"),
+    ("swift", "Swift"),
+    ("m", "Objective-C"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cxx", "C++"),
+    ("hpp", "C++"),
+    ("cs", "C#"),
+    ("fs", "F#"),
+    ("php", "PHP"),
+    ("ex", "Elixir"),
+    ("exs", "Elixir"),
+    ("erl", "Erlang"),
+    ("hs", "Haskell"),
+    ("ml", "OCaml"),
+    ("clj", "Clojure"),
+    ("lua", "Lua"),
+    ("dart", "Dart"),
+    ("zig", "Zig"),
+    ("sh", "Shell"),
+    ("bash", "Shell"),
+    ("zsh", "Shell"),
+    ("ps1", "PowerShell"),
+    ("sql", "SQL"),
+    ("html", "HTML"),
+    ("htm", "HTML"),
+    ("css", "CSS"),
+    ("scss", "SCSS"),
+    ("vue", "Vue"),
+    ("svelte", "Svelte"),
+    ("md", "Markdown"),
+    ("rst", "reStructuredText"),
+    ("json", "JSON"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("toml", "TOML"),
+    ("xml", "XML"),
+    ("proto", "Protocol Buffers"),
+    ("tf", "HCL"),
+    ("nix", "Nix"),
+    ("Dockerfile", "Dockerfile"),
+    ("Makefile", "Makefile"),
+];
+
+/// Lines counted for files no language matches.
+const OTHER: &str = "Other";
+
+/// Maps changed files to languages.
+pub struct LanguageMap {
+    languages: HashMap<String, String>,
+}
+
+impl LanguageMap {
+    pub fn new(overrides: &HashMap<String, String>) -> LanguageMap {
+        let mut languages: HashMap<String, String> = BUILTIN_LANGUAGES.iter()
+            .map(|(key, language)| (key.to_string(), language.to_string()))
+            .collect();
+        for (key, language) in overrides {
+            languages.insert(key.trim_start_matches('.').to_string(), language.clone());
+        }
+        LanguageMap { languages }
+    }
+
+    /// The language of `path`, by extension, or by file name for files
+    /// without one. Extensions are matched case-insensitively.
+    pub fn language(&self, path: &str) -> &str {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let found = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => self.languages.get(extension)
+                .or_else(|| self.languages.get(&extension.to_lowercase())),
+            _ => self.languages.get(name),
+        };
+        found.map(String::as_str).unwrap_or(OTHER)
+    }
+}
+
+/// Sums changed lines per language and orders them largest first, ties by
+/// name. Languages without changed lines are left out.
+pub fn breakdown<'a>(lines: impl IntoIterator<Item = (&'a str, u64)>) -> Vec<(String, u64)> {
+    let mut totals: HashMap<&str, u64> = HashMap::new();
+    for (language, count) in lines {
+        *totals.entry(language).or_insert(0) += count;
+    }
+    let mut breakdown: Vec<(String, u64)> = totals.into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(language, count)| (language.to_string(), count))
+        .collect();
+    breakdown.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    breakdown
+}
@@ -0,0 +1,236 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::api::{CommitDetail, CommitFile, CommitInfo, CommitStats};
+use crate::events::{group_commits, DailySummaries};
+
+/// Separators `git log` puts between fields and between commits, picked
+/// because they never show up in commit messages.
+const FIELD_SEPARATOR: char = '\u{1f}';
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+fn git(repo: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("git").arg("-C").arg(repo).args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {} failed in {}: {}", args.join(" "), repo.display(), stderr.trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The email configured in the user's global git config, if any.
+pub fn default_author_email() -> Option<String> {
+    let output = Command::new("git").args(["config", "--global", "user.email"]).output().ok()?;
+    let email = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if email.is_empty() { None } else { Some(email) }
+}
+
+/// Every git repository under `dir`. Hidden directories are skipped, and
+/// so is everything inside a repository once it's found. Symlinked
+/// directories aren't followed, so links back up the tree can't loop.
+fn find_repositories(dir: &Path, repos: &mut Vec<PathBuf>) {
+    if dir.join(".git").exists() {
+        repos.push(dir.to_path_buf());
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        log::debug!("Cannot read {}, skipping", dir.display());
+        return;
+    };
+    for entry in entries.flatten() {
+        // Unlike Path::is_dir, the entry's file type doesn't follow symlinks.
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if is_dir && !hidden {
+            find_repositories(&entry.path(), repos);
+        }
+    }
+}
+
+/// `owner/name` from the origin remote (SSH or HTTPS), falling back to the
+/// directory name for repositories without one.
+fn repository_name(repo: &Path) -> String {
+    let dir_name = repo.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let Ok(url) = git(repo, &["remote", "get-url", "origin"]) else { return dir_name };
+    let path = url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let mut parts = path.rsplit(['/', ':']);
+    match (parts.next(), parts.next()) {
+        (Some(name), Some(owner)) if !name.is_empty() && !owner.is_empty() => format!("{}/{}", owner, name),
+        _ => dir_name,
+    }
+}
+
+struct LocalCommit {
+    sha: String,
+    /// Author date in ISO 8601, with the author's offset.
+    date: String,
+    message: String,
+    files: Vec<CommitFile>,
+}
+
+/// The new path from a `--numstat` rename, e.g. `src/b.rs` for
+/// `src/{a.rs => b.rs}` or `b.rs` for `a.rs => b.rs`. Braces are only
+/// read as a rename when they hold the arrow.
+fn renamed_path(path: &str) -> String {
+    let Some(arrow) = path.find(" => ") else { return path.to_string() };
+    match (path[..arrow].rfind('{'), path[arrow..].find('}')) {
+        (Some(open), Some(close)) => {
+            let close = arrow + close;
+            let new = &path[arrow + " => ".len()..close];
+            // `{ => dir}/` and `{dir => }/` leave a doubled slash behind.
+            format!("{}{}{}", &path[..open], new, &path[close + 1..]).replace("//", "/")
+        }
+        _ => path[arrow + " => ".len()..].to_string(),
+    }
+}
+
+/// Files from `git log --numstat` lines like `12\t3\tsrc/main.rs`. Binary
+/// files show `-` for both counts and are counted as no lines.
+fn parse_numstat(text: &str) -> Vec<CommitFile> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let additions = fields.next()?;
+            let deletions = fields.next()?;
+            let path = fields.next()?;
+            Some(CommitFile {
+                filename: renamed_path(path),
+                additions: additions.parse().unwrap_or(0),
+                deletions: deletions.parse().unwrap_or(0),
+                patch: None,
+            })
+        })
+        .collect()
+}
+
+/// Commits by any of `emails` on any branch of `repo`.
+fn read_commits(
+    repo: &Path,
+    emails: &[String],
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> Result<Vec<LocalCommit>, Box<dyn Error>> {
+    // --since filters on the committer date, which is never earlier than
+    // the author date, so it only skips commits that can't be in range.
+    // The range itself is checked against the author date below.
+    let since = format!("--since={}", start_date.to_rfc3339());
+    // --numstat lines follow each commit's formatted header, so records
+    // start with the separator and the file list is the last field.
+    let format = format!("--format={1}%H{0}%ae{0}%aI{0}%B{0}", FIELD_SEPARATOR, RECORD_SEPARATOR);
+    // Several --author options match commits by any of them. They match
+    // anywhere in `Name <email>`, so they only narrow the log down and the
+    // email itself is compared below.
+    let authors: Vec<String> = emails.iter().map(|email| format!("--author={}", email)).collect();
+    let mut args = vec!["log", "--all", "--numstat", "--fixed-strings", "--regexp-ignore-case", since.as_str(), format.as_str()];
+    args.extend(authors.iter().map(String::as_str));
+    let emails: Vec<String> = emails.iter().map(|email| email.trim().to_lowercase()).collect();
+
+    let output = git(repo, &args)?;
+    Ok(output
+        .split(RECORD_SEPARATOR)
+        .filter_map(|record| {
+            let mut fields = record.trim_start().splitn(5, FIELD_SEPARATOR);
+            let sha = fields.next()?.to_string();
+            let email = fields.next()?.to_lowercase();
+            if !emails.contains(&email) {
+                return None;
+            }
+            let date = fields.next()?.to_string();
+            let message = fields.next()?.trim_end().to_string();
+            let files = parse_numstat(fields.next().unwrap_or_default());
+            Some(LocalCommit { sha, date, message, files })
+        })
+        .filter(|commit| !commit.sha.is_empty())
+        .filter(|commit| {
+            DateTime::parse_from_rfc3339(&commit.date)
+                .is_ok_and(|time| time >= start_date && time <= end_date)
+        })
+        .collect())
+}
+
+/// Reads commits by the given author emails from every git repository
+/// under `dir`, without any network access. Commits are grouped into one
+/// push per repository and day, like the other sources.
+pub fn fetch_contributions(
+    dir: &Path,
+    emails: &[String],
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    tz: Tz,
+) -> Result<DailySummaries, Box<dyn Error>> {
+    if emails.is_empty() {
+        return Err("Reading local repositories needs --author-email or a global git user.email".into());
+    }
+
+    let mut repos = Vec::new();
+    find_repositories(dir, &mut repos);
+    log::debug!("Found {} git repositories under {}", repos.len(), dir.display());
+
+    let mut local_commits = Vec::new();
+    for repo in repos {
+        let commits = match read_commits(&repo, emails, start_date, end_date) {
+            Ok(commits) => commits,
+            Err(e) => {
+                log::warn!("Skipping repository: {}", e);
+                continue;
+            }
+        };
+        if commits.is_empty() {
+            continue;
+        }
+        let repo_name = repository_name(&repo);
+        log::debug!("{} commits in {} ({})", commits.len(), repo.display(), repo_name);
+
+        for LocalCommit { sha, date, message, files } in commits {
+            let stats = CommitStats {
+                additions: files.iter().map(|file| file.additions).sum(),
+                deletions: files.iter().map(|file| file.deletions).sum(),
+            };
+            let commit = CommitDetail {
+                sha,
+                commit: CommitInfo { message, author: None },
+                html_url: None,
+                author: None,
+                stats: Some(stats),
+                files,
+                also_pushed_to: Vec::new(),
+                other_author: None,
+            };
+            local_commits.push((repo_name.clone(), date, commit));
+        }
+    }
+    Ok(group_commits("local", local_commits, tz))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_path_takes_the_new_name() {
+        assert_eq!(renamed_path("src/main.rs"), "src/main.rs");
+        assert_eq!(renamed_path("a.rs => b.rs"), "b.rs");
+        assert_eq!(renamed_path("src/{a.rs => b.rs}"), "src/b.rs");
+        assert_eq!(renamed_path("{src => lib}/mod.rs"), "lib/mod.rs");
+        assert_eq!(renamed_path("src/{ => nested}/a.rs"), "src/nested/a.rs");
+        assert_eq!(renamed_path("src/{nested => }/a.rs"), "src/a.rs");
+    }
+
+    #[test]
+    fn renamed_path_keeps_braces_that_are_not_renames() {
+        assert_eq!(renamed_path("tpl/{{name}}.rs"), "tpl/{{name}}.rs");
+        assert_eq!(renamed_path("tpl/{a}/{b.rs => c.rs}"), "tpl/{a}/c.rs");
+    }
+
+    #[test]
+    fn parse_numstat_reads_counts_and_paths() {
+        let files = parse_numstat("\n12\t3\tsrc/main.rs\n-\t-\tlogo.png\n1\t1\tsrc/{a.rs => b.rs}\n");
+        let files: Vec<(&str, u64, u64)> = files.iter()
+            .map(|file| (file.filename.as_str(), file.additions, file.deletions))
+            .collect();
+        assert_eq!(files, [("src/main.rs", 12, 3), ("logo.png", 0, 0), ("src/b.rs", 1, 1)]);
+    }
+}
@@ -1,17 +1,38 @@
-use chrono::{NaiveDate, Utc, Duration, TimeZone, Datelike};
-use clap::Parser;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc, Duration, TimeZone, Datelike};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::error::Error;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration as StdDuration;
 mod api;
+mod archive;
+mod authors;
+mod cache;
+mod config;
 mod events;
+mod gharchive;
+mod graphql;
+mod html;
+mod languages;
+mod local;
 mod logging;
+mod payload;
+mod records;
+mod report;
+mod search;
+mod stats;
 mod summary;
 
 #[derive(Parser, Debug)]
-#[command(version, about = "Summarize GitHub contributions", long_about = None)]
+#[command(version, about = "Summarize GitHub contributions", long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// GitHub username (required)
     #[arg(short = 'u', long, required = true)]
-    username: String,
+    username: Option<String>,
 
     /// Start date (YYYY-MM-DD), mutually exclusive with -m and -d
     #[arg(short = 's', long, conflicts_with_all = &["month", "day"])]
@@ -28,6 +49,204 @@ struct Args {
     /// Day (YYYY-MM-DD), sets start and end dates to that day
     #[arg(short = 'd', long, conflicts_with_all = &["start_date", "end_date", "month"])]
     day: Option<String>,
+
+    /// IANA timezone for day boundaries and times, e.g. America/Los_Angeles (default: the system's)
+    #[arg(long, value_parser = parse_timezone)]
+    tz: Option<Tz>,
+
+    /// Where contributions come from; several sources can be combined, e.g. events,search
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "events")]
+    source: Vec<Source>,
+
+    /// Directory to scan for local git repositories (adds the `local` source)
+    #[arg(long)]
+    local_repos: Option<PathBuf>,
+
+    /// Email the user commits with, to recognize their commits; repeat for aliases (default for local repositories: git's user.email)
+    #[arg(long)]
+    author_email: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "markdown")]
+    format: Format,
+
+    /// Columns for csv and tsv output, comma-separated (default: all but message)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    columns: Vec<records::Column>,
+
+    /// How to split the report into sections
+    #[arg(long, value_enum, default_value = "day")]
+    group_by: summary::GroupBy,
+
+    /// Add month or week headings between days, for long ranges (only with --group-by day)
+    #[arg(long, value_enum)]
+    subheadings: Option<summary::Subheadings>,
+
+    /// Keep commits by other authors in pushes (e.g. merges and fork syncs), marked with their author
+    #[arg(long)]
+    include_others: bool,
+
+    /// GitHub API base URL, e.g. https://github.example.com/api/v3 for Enterprise Server
+    #[arg(long, env = "GITHUB_API_URL", default_value = api::DEFAULT_API_URL)]
+    api_url: String,
+
+    /// Ignore cached responses and fetch everything again
+    #[arg(long, conflicts_with = "offline")]
+    refresh: bool,
+
+    /// Only use cached responses, never the network
+    #[arg(long)]
+    offline: bool,
+
+    /// Cache directory (default: $XDG_CACHE_HOME/gh-user-summary)
+    #[arg(long, env = "GH_USER_SUMMARY_CACHE", global = true)]
+    cache_dir: Option<PathBuf>,
+
+    /// Event archive directory (default: $XDG_DATA_HOME/gh-user-summary/archive)
+    #[arg(long, env = "GH_USER_SUMMARY_ARCHIVE", global = true)]
+    archive_dir: Option<PathBuf>,
+
+    /// Leave matching files out of line counts, e.g. '*.lock' or 'dist/**'; repeat or comma-separate
+    #[arg(long, value_delimiter = ',')]
+    exclude_files: Vec<String>,
+
+    /// Config file (default: $XDG_CONFIG_HOME/gh-user-summary/config.toml)
+    #[arg(long, env = "GH_USER_SUMMARY_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// How many API requests may be in flight at once
+    #[arg(long, default_value = "4")]
+    max_requests: NonZeroUsize,
+
+    /// How long cached pull requests are trusted before re-checking their state (e.g. 12h, 7d)
+    #[arg(long, default_value = "1d", value_parser = parse_duration)]
+    pr_ttl: StdDuration,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// A readable report, grouped by --group-by
+    Markdown,
+    /// Every day and event with its commits and pull requests, for other tools
+    Json,
+    /// One row per contribution (commit, PR, comment, ...), for spreadsheets
+    Csv,
+    /// Like csv, separated by tabs
+    Tsv,
+    /// A single self-contained page with a calendar heatmap and charts
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Source {
+    /// The REST events feed (last 90 days), plus the local archive
+    Events,
+    /// GraphQL contributionsCollection, works for any range but only has commit counts
+    Graphql,
+    /// Search API for authored PRs, issues and commits, works for any range
+    Search,
+    /// Commit logs of the git repositories under --local-repos, works offline
+    Local,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect and maintain the response cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Save all events the API currently returns into the local archive.
+    /// Run it regularly (e.g. weekly from cron) to keep history past the
+    /// API's 90-day / 300-event window.
+    Sync {
+        /// GitHub username
+        #[arg(short = 'u', long)]
+        username: String,
+    },
+    /// Import a user's events from GH Archive dumps (https://www.gharchive.org)
+    /// into the local archive, for ranges older than the API can reach.
+    ImportGharchive {
+        /// GitHub username
+        #[arg(short = 'u', long)]
+        username: String,
+
+        /// Hourly `.json.gz` files downloaded from GH Archive
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// List every cached entry with its age and size
+    Ls,
+    /// Show entry counts and sizes by key prefix (events:, commit:, pr:)
+    Stats,
+    /// Remove everything from the cache
+    Clear,
+    /// Remove entries older than the given age
+    Prune {
+        /// Age like 12h or 30d
+        #[arg(long, value_parser = parse_duration)]
+        older_than: StdDuration,
+    },
+    /// Check every entry's content and remove corrupt ones
+    Verify,
+}
+
+fn run_cache_command(action: &CacheAction, cache_dir: &Path) -> Result<(), Box<dyn Error>> {
+    match action {
+        CacheAction::Ls => cache::list(cache_dir),
+        CacheAction::Stats => cache::stats(cache_dir),
+        CacheAction::Clear => cache::clear(cache_dir),
+        CacheAction::Prune { older_than } => cache::prune(cache_dir, *older_than),
+        CacheAction::Verify => cache::verify(cache_dir),
+    }
+}
+
+/// Parses durations like `45s`, `30m`, `12h` or `7d`.
+fn parse_duration(value: &str) -> Result<StdDuration, String> {
+    let split = value.len() - value.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("invalid duration '{}'", value))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("invalid duration unit in '{}', expected s, m, h or d", value)),
+    };
+    let total = number.checked_mul(seconds).ok_or_else(|| format!("duration '{}' is too long", value))?;
+    Ok(StdDuration::from_secs(total))
+}
+
+fn parse_timezone(value: &str) -> Result<Tz, String> {
+    value.parse().map_err(|_| format!("unknown timezone '{}', expected an IANA name like Europe/Berlin", value))
+}
+
+/// The system's timezone, or UTC if it can't be found or isn't in the
+/// timezone database.
+fn system_timezone() -> Tz {
+    match iana_time_zone::get_timezone() {
+        Ok(name) => name.parse().unwrap_or_else(|_| {
+            log::warn!("Unknown system timezone {}, using UTC", name);
+            Tz::UTC
+        }),
+        Err(e) => {
+            log::debug!("Cannot find the system timezone ({}), using UTC", e);
+            Tz::UTC
+        }
+    }
+}
+
+/// A wall-clock time in `tz` as UTC. Times skipped by a DST change move
+/// past the gap, repeated ones use the earlier instant.
+fn in_timezone(tz: Tz, time: NaiveDateTime) -> Result<DateTime<Utc>, Box<dyn Error>> {
+    let local = tz.from_local_datetime(&time).earliest()
+        .or_else(|| tz.from_local_datetime(&(time + Duration::hours(1))).earliest())
+        .ok_or_else(|| format!("{} doesn't exist in {}", time, tz))?;
+    Ok(local.with_timezone(&Utc))
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -37,6 +256,10 @@ fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
     log::debug!("Command line args: {:?}", args);
 
+    let cache_root = args.cache_dir.clone().unwrap_or_else(cache::default_root);
+    if let Some(Command::Cache { action }) = &args.command {
+        return run_cache_command(action, &cache_root);
+    }
     let token = std::env::var("GITHUB_TOKEN").unwrap_or_default();
     if token.is_empty() {
         log::warn!("No GITHUB_TOKEN found in environment");
@@ -44,16 +267,61 @@ fn main() -> Result<(), Box<dyn Error>> {
         log::debug!("GITHUB_TOKEN found (length: {})", token.len());
     }
 
-    let client = api::create_client()?;
+    let cache_mode = if args.refresh {
+        api::CacheMode::Refresh
+    } else if args.offline {
+        api::CacheMode::Offline
+    } else {
+        api::CacheMode::Normal
+    };
+    let archive_root = args.archive_dir.clone().unwrap_or_else(archive::default_root);
+    let archive_dir = archive::archive_dir(&archive_root, &args.api_url, &token);
+    // Events anyone can see, which every identity also reads.
+    let public_archive_dir = archive::archive_dir(&archive_root, &args.api_url, "");
+    if let Some(Command::ImportGharchive { username, files }) = &args.command {
+        // GH Archive only has public events.
+        let mut added = 0;
+        for file in files {
+            let events = gharchive::read_events(file, username)?;
+            added += archive::store(&public_archive_dir, username, &events)?;
+        }
+        println!("Archived {} new events for {} from {} file(s) in {}", added, username, files.len(), public_archive_dir.display());
+        return Ok(());
+    }
+
+    let cache = api::CacheConfig {
+        dir: cache::namespace_dir(&cache_root, &args.api_url, &token),
+        mode: cache_mode,
+        pr_ttl: args.pr_ttl,
+    };
+    let client = api::create_client(&args.api_url, &token, cache, args.max_requests.get())?;
+
+    if let Some(Command::Sync { username }) = &args.command {
+        let events = api::fetch_all_events(&client, username, DateTime::<Utc>::MIN_UTC)?;
+        let added = archive::store(&archive_dir, username, &events)?;
+        println!("Archived {} new of {} fetched events for {} in {}", added, events.len(), username, archive_dir.display());
+        client.log_rate_limit();
+        return Ok(());
+    }
+
+    // clap only makes --username optional when a subcommand is given.
+    let username = args.username.as_deref().ok_or("Missing --username")?;
+    // --group-by has a default, so clap can't tell an explicit `day` apart.
+    if args.subheadings.is_some() && args.group_by != summary::GroupBy::Day {
+        return Err("--subheadings only applies with --group-by day".into());
+    }
+
+    let tz = args.tz.unwrap_or_else(system_timezone);
+    log::debug!("Using timezone {}", tz);
 
-    // Determine start_date and end_date based on args
+    // Determine start_date and end_date based on args, as whole days in tz
     let (start_date, end_date) = match (&args.start_date, &args.end_date, &args.month, &args.day) {
         (Some(start), Some(end), None, None) => {
             let start_date = NaiveDate::parse_from_str(start, "%Y-%m-%d")?;
             let end_date = NaiveDate::parse_from_str(end, "%Y-%m-%d")?;
             (
-                Utc.from_utc_datetime(&start_date.and_hms_opt(0, 0, 0).unwrap()),
-                Utc.from_utc_datetime(&end_date.and_hms_opt(23, 59, 59).unwrap())
+                in_timezone(tz, start_date.and_hms_opt(0, 0, 0).unwrap())?,
+                in_timezone(tz, end_date.and_hms_opt(23, 59, 59).unwrap())?
             )
         }
         (None, None, Some(month), None) => {
@@ -64,15 +332,15 @@ fn main() -> Result<(), Box<dyn Error>> {
                 NaiveDate::from_ymd_opt(naive_date.year(), naive_date.month() + 1, 1)
             }.unwrap();
             (
-                Utc.from_utc_datetime(&naive_date.and_hms_opt(0, 0, 0).unwrap()),
-                Utc.from_utc_datetime(&(next_month - Duration::days(1)).and_hms_opt(23, 59, 59).unwrap())
+                in_timezone(tz, naive_date.and_hms_opt(0, 0, 0).unwrap())?,
+                in_timezone(tz, (next_month - Duration::days(1)).and_hms_opt(23, 59, 59).unwrap())?
             )
         }
         (None, None, None, Some(day)) => {
             let naive_date = NaiveDate::parse_from_str(day, "%Y-%m-%d")?;
             (
-                Utc.from_utc_datetime(&naive_date.and_hms_opt(0, 0, 0).unwrap()),
-                Utc.from_utc_datetime(&naive_date.and_hms_opt(23, 59, 59).unwrap())
+                in_timezone(tz, naive_date.and_hms_opt(0, 0, 0).unwrap())?,
+                in_timezone(tz, naive_date.and_hms_opt(23, 59, 59).unwrap())?
             )
         }
         (None, None, None, None) => {
@@ -87,9 +355,104 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     log::debug!("Target range - Start: {}, End: {}", start_date, end_date);
 
-    let events = api::fetch_all_events(&client, &args.username, &token, start_date)?;
-    let daily_summaries = events::process_events(&client, &token, events, start_date, end_date)?;
-    summary::print_summaries(daily_summaries, start_date, end_date)?;
+    let config = config::load(args.config.as_deref())?;
+    let file_filter = stats::FileFilter::new(&args.exclude_files)?;
+    let language_map = languages::LanguageMap::new(&config.languages);
+    let mut author_emails = args.author_email.clone();
+    author_emails.extend(config.author_emails.iter().cloned());
+    let identity = authors::Identity::new(username, &author_emails);
+
+    let mut sources = args.source.clone();
+    if args.local_repos.is_some() && !sources.contains(&Source::Local) {
+        sources.push(Source::Local);
+    }
+
+    let mut daily_summaries = events::DailySummaries::new();
+    for source in &sources {
+        let source_summaries = match source {
+            Source::Events => {
+                // Without the API, the archive still has everything seen before.
+                let mut events = if args.offline {
+                    Vec::new()
+                } else {
+                    match api::fetch_all_events(&client, username, start_date) {
+                        Ok(events) => events,
+                        Err(e) => {
+                            log::warn!("Couldn't fetch events, using the archive only: {}", e);
+                            Vec::new()
+                        }
+                    }
+                };
+                archive::store(&archive_dir, username, &events)?;
+                // The API only reaches back 90 days / 300 events; older parts of the
+                // range can only come from the archive.
+                let api_window_start = events.last().and_then(|event| DateTime::parse_from_rfc3339(&event.created_at).ok());
+                if api_window_start.is_none_or(|oldest| oldest > start_date) {
+                    let mut archived = archive::load(&archive_dir, username, start_date, end_date)?;
+                    if public_archive_dir != archive_dir {
+                        archived = archive::merge(archived, archive::load(&public_archive_dir, username, start_date, end_date)?);
+                    }
+                    log::debug!("Range predates the API window, adding {} archived events", archived.len());
+                    events = archive::merge(events, archived);
+                }
+                events::process_events(&client, events, start_date, end_date, &identity, args.include_others, tz)?
+            }
+            Source::Graphql => graphql::fetch_contributions(&client, username, start_date, end_date, tz)?,
+            Source::Search => search::fetch_contributions(&client, username, start_date, end_date, tz)?,
+            Source::Local => {
+                let dir = args.local_repos.as_deref().ok_or("The local source needs --local-repos")?;
+                let mut emails = author_emails.clone();
+                if emails.is_empty() {
+                    emails.extend(local::default_author_email());
+                }
+                local::fetch_contributions(dir, &emails, start_date, end_date, tz)?
+            }
+        };
+        events::merge_summaries(&mut daily_summaries, source_summaries);
+    }
+    let report = report::build(daily_summaries, start_date, end_date, tz, &file_filter, &language_map);
+    match args.format {
+        Format::Markdown => {
+            let layout = summary::Layout { group_by: args.group_by, subheadings: args.subheadings };
+            summary::print_summaries(&report, &layout)?;
+        }
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        Format::Csv | Format::Tsv => {
+            let columns = if args.columns.is_empty() { records::DEFAULT_COLUMNS } else { &args.columns };
+            let delimiter = if args.format == Format::Tsv { b'\t' } else { b',' };
+            records::write_records(&report, columns, delimiter)?;
+        }
+        Format::Html => print!("{}", html::render(&report)?),
+    }
+    client.log_rate_limit();
 
     Ok(())
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_reads_each_unit() {
+        assert_eq!(parse_duration("45s"), Ok(StdDuration::from_secs(45)));
+        assert_eq!(parse_duration("30m"), Ok(StdDuration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("12h"), Ok(StdDuration::from_secs(12 * 60 * 60)));
+        assert_eq!(parse_duration("7d"), Ok(StdDuration::from_secs(7 * 24 * 60 * 60)));
+    }
+
+    #[test]
+    fn parse_duration_rejects_bad_input() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("10").is_err());
+        assert!(parse_duration("10w").is_err());
+        assert!(parse_duration("-1d").is_err());
+    }
+
+    #[test]
+    fn parse_duration_rejects_overflow_instead_of_wrapping() {
+        assert_eq!(parse_duration("213503982334601d"), Ok(StdDuration::from_secs(213503982334601 * 24 * 60 * 60)));
+        assert_eq!(parse_duration("213503982334602d"), Err("duration '213503982334602d' is too long".to_string()));
+        assert!(parse_duration("18446744073709551616s").is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+
+/// An event's payload, picked by the event's `type` field. Types we don't
+/// model keep their raw JSON in `Unknown`.
+#[derive(Debug, Clone)]
+pub enum Event {
+    Push(PushPayload),
+    PullRequest(PullRequestPayload),
+    Create(RefPayload),
+    Delete(RefPayload),
+    Issues(IssuesPayload),
+    IssueComment(IssueCommentPayload),
+    PullRequestReview(PullRequestReviewPayload),
+    PullRequestReviewComment(PullRequestReviewCommentPayload),
+    Release(ReleasePayload),
+    Fork(ForkPayload),
+    Watch(WatchPayload),
+    Member(MemberPayload),
+    /// A repository was made public; the payload is empty.
+    Public,
+    Gollum(GollumPayload),
+    Unknown { event_type: String, payload: Value },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct PushPayload {
+    #[serde(rename = "ref", default)]
+    pub git_ref: Option<String>,
+    /// Number of commits in the push, which can be more than `commits` holds.
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub commits: Vec<PushCommit>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PushCommit {
+    pub sha: String,
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub author: Option<PushCommitAuthor>,
+    #[serde(default)]
+    pub distinct: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PushCommitAuthor {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub email: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PullRequestPayload {
+    pub action: String,
+    pub number: i64,
+    #[serde(default)]
+    pub pull_request: Option<PullRequestSummary>,
+}
+
+/// The parts of a pull request that events embed.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PullRequestSummary {
+    pub number: i64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct RefPayload {
+    /// Missing when a whole repository is created.
+    #[serde(rename = "ref", default)]
+    pub git_ref: Option<String>,
+    pub ref_type: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct IssuesPayload {
+    pub action: String,
+    pub issue: IssueSummary,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct IssueSummary {
+    pub number: i64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub html_url: String,
+    /// Only present when the issue is a pull request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<PullRequestLink>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PullRequestLink {
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct IssueCommentPayload {
+    pub action: String,
+    pub issue: IssueSummary,
+    pub comment: Comment,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Comment {
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PullRequestReviewPayload {
+    pub action: String,
+    pub review: Review,
+    pub pull_request: PullRequestSummary,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Review {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    /// `approved`, `changes_requested`, `commented` or `dismissed`.
+    pub state: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct PullRequestReviewCommentPayload {
+    pub action: String,
+    pub comment: ReviewComment,
+    pub pull_request: PullRequestSummary,
+}
+
+/// An inline comment on a pull request's diff.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReviewComment {
+    /// The review the comment was submitted with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request_review_id: Option<u64>,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ReleasePayload {
+    pub action: String,
+    pub release: Release,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ForkPayload {
+    /// The new fork.
+    pub forkee: Forkee,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Forkee {
+    pub full_name: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+/// Despite the name, a star. The starred repository is the event's repo.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WatchPayload {
+    pub action: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct MemberPayload {
+    pub action: String,
+    pub member: Member,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Member {
+    pub login: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+/// Wiki edits, one entry per page touched.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct GollumPayload {
+    pub pages: Vec<WikiPage>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WikiPage {
+    #[serde(default)]
+    pub title: String,
+    /// `created` or `edited`.
+    pub action: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+fn parse<T: DeserializeOwned>(event_type: &str, payload: &Value) -> Option<T> {
+    match serde_json::from_value(payload.clone()) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            log::debug!("Unexpected {} payload ({}), keeping it as unknown", event_type, e);
+            None
+        }
+    }
+}
+
+impl Event {
+    /// Picks the payload type from the event's `type` field. A payload that
+    /// doesn't match its type's shape is kept as `Unknown` rather than
+    /// failing the whole page of events.
+    pub fn from_parts(event_type: String, payload: Value) -> Event {
+        let parsed = match event_type.as_str() {
+            "PushEvent" => parse(&event_type, &payload).map(Event::Push),
+            "PullRequestEvent" => parse(&event_type, &payload).map(Event::PullRequest),
+            "CreateEvent" => parse(&event_type, &payload).map(Event::Create),
+            "DeleteEvent" => parse(&event_type, &payload).map(Event::Delete),
+            "IssuesEvent" => parse(&event_type, &payload).map(Event::Issues),
+            "IssueCommentEvent" => parse(&event_type, &payload).map(Event::IssueComment),
+            "PullRequestReviewEvent" => parse(&event_type, &payload).map(Event::PullRequestReview),
+            "PullRequestReviewCommentEvent" => parse(&event_type, &payload).map(Event::PullRequestReviewComment),
+            "ReleaseEvent" => parse(&event_type, &payload).map(Event::Release),
+            "ForkEvent" => parse(&event_type, &payload).map(Event::Fork),
+            "WatchEvent" => parse(&event_type, &payload).map(Event::Watch),
+            "MemberEvent" => parse(&event_type, &payload).map(Event::Member),
+            "PublicEvent" => Some(Event::Public),
+            "GollumEvent" => parse(&event_type, &payload).map(Event::Gollum),
+            _ => None,
+        };
+        parsed.unwrap_or(Event::Unknown { event_type, payload })
+    }
+
+    /// The event's `type` and payload as the API would send them.
+    pub fn to_parts(&self) -> (String, Value) {
+        let payload = match self {
+            Event::Push(payload) => serde_json::to_value(payload),
+            Event::PullRequest(payload) => serde_json::to_value(payload),
+            Event::Create(payload) | Event::Delete(payload) => serde_json::to_value(payload),
+            Event::Issues(payload) => serde_json::to_value(payload),
+            Event::IssueComment(payload) => serde_json::to_value(payload),
+            Event::PullRequestReview(payload) => serde_json::to_value(payload),
+            Event::PullRequestReviewComment(payload) => serde_json::to_value(payload),
+            Event::Release(payload) => serde_json::to_value(payload),
+            Event::Fork(payload) => serde_json::to_value(payload),
+            Event::Watch(payload) => serde_json::to_value(payload),
+            Event::Member(payload) => serde_json::to_value(payload),
+            Event::Public => Ok(Value::Object(Default::default())),
+            Event::Gollum(payload) => serde_json::to_value(payload),
+            Event::Unknown { payload, .. } => Ok(payload.clone()),
+        };
+        (self.type_name().to_string(), payload.unwrap_or(Value::Null))
+    }
+
+    /// The API's name for the event type, e.g. `PushEvent`.
+    pub fn type_name(&self) -> &str {
+        match self {
+            Event::Push(_) => "PushEvent",
+            Event::PullRequest(_) => "PullRequestEvent",
+            Event::Create(_) => "CreateEvent",
+            Event::Delete(_) => "DeleteEvent",
+            Event::Issues(_) => "IssuesEvent",
+            Event::IssueComment(_) => "IssueCommentEvent",
+            Event::PullRequestReview(_) => "PullRequestReviewEvent",
+            Event::PullRequestReviewComment(_) => "PullRequestReviewCommentEvent",
+            Event::Release(_) => "ReleaseEvent",
+            Event::Fork(_) => "ForkEvent",
+            Event::Watch(_) => "WatchEvent",
+            Event::Member(_) => "MemberEvent",
+            Event::Public => "PublicEvent",
+            Event::Gollum(_) => "GollumEvent",
+            Event::Unknown { event_type, .. } => event_type,
+        }
+    }
+}
+
+/// Serializes as the payload alone, the way the API sends it.
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_parts().1.serialize(serializer)
+    }
+}
@@ -0,0 +1,238 @@
+use clap::ValueEnum;
+use std::error::Error;
+use std::io;
+use crate::payload::Event;
+use crate::report::{Report, ReportEvent};
+
+/// A column of the CSV and TSV output.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// When the event happened, RFC 3339 in the report's timezone
+    Timestamp,
+    /// The day the contribution is counted under
+    Day,
+    Repo,
+    /// The API's event type, e.g. PushEvent
+    Type,
+    /// What happened, e.g. opened or closed; a review's outcome
+    Action,
+    /// Branch, tag or release pushed, created or deleted
+    Ref,
+    /// Commit SHA, for pushes
+    Sha,
+    /// Pull request number, for pull requests, reviews and PR comments
+    Pr,
+    /// First line of the commit message, or the PR, issue, release or page title
+    Title,
+    /// The full commit message or comment body, which can span lines
+    Message,
+    Url,
+}
+
+/// Columns written when none are chosen.
+pub const DEFAULT_COLUMNS: &[Column] = &[
+    Column::Timestamp,
+    Column::Day,
+    Column::Repo,
+    Column::Type,
+    Column::Action,
+    Column::Ref,
+    Column::Sha,
+    Column::Pr,
+    Column::Title,
+    Column::Url,
+];
+
+/// One contribution: a commit, or an event that isn't a push of commits.
+struct Record<'a> {
+    event: &'a ReportEvent,
+    action: String,
+    git_ref: String,
+    sha: String,
+    pr: Option<i64>,
+    title: String,
+    message: String,
+    url: String,
+}
+
+impl<'a> Record<'a> {
+    /// A row with only the event's own columns filled in.
+    fn new(event: &'a ReportEvent) -> Record<'a> {
+        Record {
+            event,
+            action: String::new(),
+            git_ref: String::new(),
+            sha: String::new(),
+            pr: None,
+            title: String::new(),
+            message: String::new(),
+            url: String::new(),
+        }
+    }
+
+    fn field(&self, column: Column) -> String {
+        let event = self.event;
+        match column {
+            Column::Timestamp => event.created_at.clone(),
+            Column::Day => event.date.clone(),
+            Column::Repo => event.repo.clone(),
+            Column::Type => event.event_type.clone(),
+            Column::Action => self.action.clone(),
+            Column::Ref => self.git_ref.clone(),
+            Column::Sha => self.sha.clone(),
+            Column::Pr => self.pr.map(|number| number.to_string()).unwrap_or_default(),
+            Column::Title => self.title.clone(),
+            Column::Message => self.message.clone(),
+            Column::Url => self.url.clone(),
+        }
+    }
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+/// `main` for `refs/heads/main`; other refs are kept as they are.
+fn branch(git_ref: Option<&str>) -> String {
+    let git_ref = git_ref.unwrap_or_default();
+    git_ref.strip_prefix("refs/heads/").unwrap_or(git_ref).to_string()
+}
+
+/// The rows for one event: a push gives one per commit, a wiki edit one
+/// per page, anything else a single row.
+fn records(event: &ReportEvent) -> Vec<Record<'_>> {
+    match &event.payload {
+        Event::Push(push) if !event.commits.is_empty() => event.commits.iter()
+            .map(|commit| Record {
+                git_ref: branch(push.git_ref.as_deref()),
+                sha: commit.sha.clone(),
+                title: first_line(&commit.message),
+                message: commit.message.clone(),
+                ..Record::new(event)
+            })
+            .collect(),
+        Event::Push(push) => vec![Record {
+            git_ref: branch(push.git_ref.as_deref()),
+            title: format!("{} commit(s)", push.size),
+            ..Record::new(event)
+        }],
+        Event::PullRequest(pull_request) => {
+            let (title, url) = match (&event.pull_request, &pull_request.pull_request) {
+                (Some(pr), _) => (pr.title.clone(), pr.url.clone()),
+                (None, Some(pr)) => (pr.title.clone(), pr.html_url.clone()),
+                (None, None) => Default::default(),
+            };
+            vec![Record { action: pull_request.action.clone(), pr: Some(pull_request.number), title, url, ..Record::new(event) }]
+        }
+        Event::Create(created) | Event::Delete(created) => vec![Record {
+            action: created.ref_type.clone(),
+            git_ref: created.git_ref.clone().unwrap_or_default(),
+            ..Record::new(event)
+        }],
+        Event::Issues(issues) => vec![Record {
+            action: issues.action.clone(),
+            title: issues.issue.title.clone(),
+            url: issues.issue.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::IssueComment(comment) => vec![Record {
+            action: comment.action.clone(),
+            pr: comment.issue.pull_request.as_ref().map(|_| comment.issue.number),
+            title: comment.issue.title.clone(),
+            message: comment.comment.body.clone(),
+            url: comment.comment.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::PullRequestReview(review) => vec![Record {
+            action: review.review.state.clone(),
+            pr: Some(review.pull_request.number),
+            title: review.pull_request.title.clone(),
+            url: review.review.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::PullRequestReviewComment(comment) => vec![Record {
+            action: comment.action.clone(),
+            pr: Some(comment.pull_request.number),
+            title: comment.pull_request.title.clone(),
+            message: comment.comment.body.clone(),
+            url: comment.comment.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::Release(release) => vec![Record {
+            action: release.action.clone(),
+            git_ref: release.release.tag_name.clone(),
+            title: release.release.name.clone().filter(|name| !name.is_empty())
+                .unwrap_or_else(|| release.release.tag_name.clone()),
+            url: release.release.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::Fork(fork) => vec![Record {
+            title: fork.forkee.full_name.clone(),
+            url: fork.forkee.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::Watch(watch) => vec![Record { action: watch.action.clone(), ..Record::new(event) }],
+        Event::Member(member) => vec![Record {
+            action: member.action.clone(),
+            title: member.member.login.clone(),
+            url: member.member.html_url.clone(),
+            ..Record::new(event)
+        }],
+        Event::Gollum(gollum) => gollum.pages.iter()
+            .map(|page| Record {
+                action: page.action.clone(),
+                title: page.title.clone(),
+                url: page.html_url.clone(),
+                ..Record::new(event)
+            })
+            .collect(),
+        Event::Public | Event::Unknown { .. } => vec![Record::new(event)],
+    }
+}
+
+/// Spreadsheets run cells starting with these as formulas.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// `field` with a leading `'` if a spreadsheet would otherwise treat it as
+/// a formula, so a commit message like `=HYPERLINK(...)` stays text.
+fn defuse_formula(field: String) -> String {
+    if field.starts_with(FORMULA_PREFIXES) {
+        format!("'{}", field)
+    } else {
+        field
+    }
+}
+
+/// Writes one row per contribution to stdout, oldest first, with a header
+/// row. Fields with delimiters, quotes or line breaks are quoted, so
+/// multi-line messages stay in one cell.
+pub fn write_records(report: &Report, columns: &[Column], delimiter: u8) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::WriterBuilder::new().delimiter(delimiter).from_writer(io::stdout());
+    writer.write_record(columns.iter().filter_map(|column| column.to_possible_value()).map(|value| value.get_name().to_string()))?;
+    for event in report.days.iter().flat_map(|day| &day.events) {
+        for record in records(event) {
+            writer.write_record(columns.iter().map(|column| defuse_formula(record.field(*column))))?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defuse_formula_prefixes_formula_starts() {
+        for field in ["=HYPERLINK(\"x\")", "+1", "-1", "@SUM(A1)", "\tx", "\rx"] {
+            assert_eq!(defuse_formula(field.to_string()), format!("'{}", field));
+        }
+    }
+
+    #[test]
+    fn defuse_formula_leaves_other_fields_alone() {
+        for field in ["", "Fix = bug", "octo/repo", "2024-02-10", "'quoted"] {
+            assert_eq!(defuse_formula(field.to_string()), field);
+        }
+    }
+}
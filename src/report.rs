@@ -0,0 +1,257 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::api::{CommitDetail, PullRequestDetail};
+use crate::events::DailySummaries;
+use crate::languages::{breakdown, LanguageMap};
+use crate::payload::Event;
+use crate::stats::{commit_lines, FileFilter, LineTotals};
+
+/// Version of the JSON output. Bumped when a field is renamed, removed or
+/// changes meaning; adding fields keeps the version.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Everything the summary shows, independent of how it's rendered.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub schema_version: u32,
+    pub range: DateRange,
+    pub totals: Totals,
+    /// Days with activity, oldest first.
+    pub days: Vec<Day>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DateRange {
+    /// First day, `YYYY-MM-DD`.
+    pub start: String,
+    /// Last day, `YYYY-MM-DD`, included.
+    pub end: String,
+    /// IANA name of the timezone days and times are in.
+    pub timezone: String,
+}
+
+/// Counts over a set of events: a day, the whole range, or any grouping a
+/// renderer makes.
+#[derive(Serialize, Debug, Default)]
+pub struct Totals {
+    pub events: usize,
+    pub active_days: usize,
+    pub event_types: BTreeMap<String, usize>,
+    pub commits: usize,
+    /// Over the commits whose line counts are known, `None` if there are none.
+    pub lines: Option<LineTotals>,
+    /// Changed lines (additions plus deletions) per language, most first.
+    pub languages: Vec<LanguageLines>,
+    /// Reviews given per outcome, e.g. `approved` or `changes_requested`.
+    pub reviews: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LanguageLines {
+    pub language: String,
+    pub lines: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Day {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub totals: Totals,
+    /// Oldest first.
+    pub events: Vec<ReportEvent>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportEvent {
+    pub id: String,
+    /// The API's event type, e.g. `PushEvent`.
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub repo: String,
+    /// RFC 3339, in the report's timezone.
+    pub created_at: String,
+    #[serde(skip)]
+    pub time: DateTime<Tz>,
+    /// The day the event is listed under.
+    #[serde(skip)]
+    pub date: String,
+    /// The event's payload, shaped by its type like in the GitHub API.
+    pub payload: Event,
+    pub commits: Vec<ReportCommit>,
+    pub pull_request: Option<ReportPullRequest>,
+    /// Inline comments submitted with a review. Only set for reviews.
+    pub review_comments: Option<usize>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportCommit {
+    pub sha: String,
+    pub message: String,
+    pub repo: String,
+    /// The commit's page on GitHub, `None` for local commits.
+    pub url: Option<String>,
+    /// The author, when it's someone other than the user (`--include-others`).
+    pub other_author: Option<String>,
+    /// Other branches the commit was pushed to later in the range.
+    pub also_pushed_to: Vec<String>,
+    /// Without excluded files. `None` when the source has no line counts.
+    pub stats: Option<CommitStats>,
+    /// Changed files, without excluded ones.
+    pub files: Vec<ReportFile>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommitStats {
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportFile {
+    pub path: String,
+    pub additions: u64,
+    pub deletions: u64,
+    pub language: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReportPullRequest {
+    pub number: i32,
+    pub title: String,
+    pub state: String,
+    pub merged: bool,
+    pub url: String,
+}
+
+impl Totals {
+    pub fn of<'a>(events: impl IntoIterator<Item = &'a ReportEvent>) -> Totals {
+        let mut totals = Totals::default();
+        let mut days: HashSet<&str> = HashSet::new();
+        let mut lines: Option<LineTotals> = None;
+        let mut languages: Vec<(&str, u64)> = Vec::new();
+
+        for event in events {
+            totals.events += 1;
+            days.insert(&event.date);
+            *totals.event_types.entry(event.event_type.clone()).or_insert(0) += 1;
+            totals.commits += event.commits.len();
+            for commit in &event.commits {
+                let Some(stats) = &commit.stats else { continue };
+                let paths = commit.files.iter().map(|file| file.path.as_str());
+                lines.get_or_insert_with(LineTotals::default).add(&commit.repo, stats.additions, stats.deletions, paths);
+                languages.extend(commit.files.iter().map(|file| (file.language.as_str(), file.additions + file.deletions)));
+            }
+            if let Event::PullRequestReview(review) = &event.payload {
+                *totals.reviews.entry(review.review.state.clone()).or_insert(0) += 1;
+            }
+        }
+
+        totals.active_days = days.len();
+        totals.lines = lines;
+        totals.languages = breakdown(languages).into_iter()
+            .map(|(language, lines)| LanguageLines { language, lines })
+            .collect();
+        totals
+    }
+}
+
+fn report_commit(commit: CommitDetail, repo: &str, filter: &FileFilter, languages: &LanguageMap) -> ReportCommit {
+    let lines = commit_lines(&commit, filter);
+    let stats = lines.as_ref().map(|lines| CommitStats { additions: lines.additions, deletions: lines.deletions });
+    let files = lines.map(|lines| lines.files).unwrap_or_default().into_iter()
+        .map(|file| ReportFile {
+            path: file.filename.clone(),
+            additions: file.additions,
+            deletions: file.deletions,
+            language: languages.language(&file.filename).to_string(),
+        })
+        .collect();
+    ReportCommit {
+        sha: commit.sha,
+        message: commit.commit.message,
+        repo: repo.to_string(),
+        url: commit.html_url,
+        other_author: commit.other_author,
+        also_pushed_to: commit.also_pushed_to,
+        stats,
+        files,
+    }
+}
+
+fn report_pull_request(pr: PullRequestDetail) -> ReportPullRequest {
+    ReportPullRequest { number: pr.number, title: pr.title, state: pr.state, merged: pr.merged, url: pr.html_url }
+}
+
+/// Builds the report for `start_date..=end_date` from every source's
+/// events. Excluded files are left out of line counts and languages here,
+/// so renderers don't need to know about them.
+pub fn build(
+    daily_summaries: DailySummaries,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    tz: Tz,
+    filter: &FileFilter,
+    languages: &LanguageMap,
+) -> Report {
+    let mut review_comments: HashMap<u64, usize> = HashMap::new();
+    for (event, _, _) in daily_summaries.values().flatten() {
+        if let Event::PullRequestReviewComment(comment) = &event.payload {
+            if let Some(review_id) = comment.comment.pull_request_review_id {
+                *review_comments.entry(review_id).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let start = start_date.with_timezone(&tz).format("%Y-%m-%d").to_string();
+    let end = end_date.with_timezone(&tz).format("%Y-%m-%d").to_string();
+    let mut days: Vec<Day> = Vec::new();
+    for (date, details) in daily_summaries.into_iter().collect::<BTreeMap<_, _>>() {
+        if date < start || date > end {
+            log::debug!("Leaving out {} events on {}, outside the range", details.len(), date);
+            continue;
+        }
+        let mut events: Vec<ReportEvent> = Vec::new();
+        for (event, commits, pr_detail) in details {
+            let time = match DateTime::parse_from_rfc3339(&event.created_at) {
+                Ok(time) => time.with_timezone(&tz),
+                Err(e) => {
+                    log::warn!("Failed to parse event time {}: {}", event.created_at, e);
+                    continue;
+                }
+            };
+            let review_comments = match &event.payload {
+                Event::PullRequestReview(review) => {
+                    Some(review.review.id.and_then(|id| review_comments.get(&id)).copied().unwrap_or(0))
+                }
+                _ => None,
+            };
+            events.push(ReportEvent {
+                id: event.id,
+                event_type: event.payload.type_name().to_string(),
+                created_at: time.to_rfc3339_opts(SecondsFormat::Secs, true),
+                time,
+                date: date.clone(),
+                commits: commits.into_iter()
+                    .map(|commit| report_commit(commit, &event.repo.name, filter, languages))
+                    .collect(),
+                repo: event.repo.name,
+                payload: event.payload,
+                pull_request: pr_detail.map(report_pull_request),
+                review_comments,
+            });
+        }
+        events.sort_by_key(|event| event.time);
+        let totals = Totals::of(&events);
+        days.push(Day { date, totals, events });
+    }
+
+    let totals = Totals::of(days.iter().flat_map(|day| &day.events));
+    Report {
+        schema_version: SCHEMA_VERSION,
+        range: DateRange { start, end, timezone: tz.name().to_string() },
+        totals,
+        days,
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use crate::api::{fetch_search_page, ApiClient, CommitDetail, CommitInfo, GitHubEvent, PullRequestDetail, Repository};
+use crate::events::{group_commits, DailySummaries, EventDetail};
+use crate::payload::{Event, IssueSummary, IssuesPayload, PullRequestPayload};
+
+/// The Search API never returns more than this many results for one query,
+/// however many pages are requested.
+const MAX_SEARCH_RESULTS: u64 = 1000;
+
+#[derive(Deserialize, Debug)]
+struct SearchPage<T> {
+    total_count: u64,
+    items: Vec<T>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IssueItem {
+    number: i32,
+    title: String,
+    body: Option<String>,
+    state: String,
+    html_url: String,
+    created_at: String,
+    /// `https://api.github.com/repos/{owner}/{repo}`
+    repository_url: String,
+    /// Only present when the item is a pull request.
+    pull_request: Option<PullRequestLink>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PullRequestLink {
+    merged_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitItem {
+    sha: String,
+    #[serde(default)]
+    html_url: Option<String>,
+    commit: SearchCommit,
+    repository: SearchRepository,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchCommit {
+    message: String,
+    author: CommitAuthor,
+}
+
+#[derive(Deserialize, Debug)]
+struct CommitAuthor {
+    date: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchRepository {
+    full_name: String,
+}
+
+fn search_date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Runs a search for `from..to`, following every page. If the range has
+/// more results than the Search API will return, it is split in half and
+/// each half searched separately.
+fn search_range<T: DeserializeOwned>(
+    api: &ApiClient,
+    endpoint: &str,
+    query: &dyn Fn(&str) -> String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Vec<T>, Box<dyn Error>> {
+    let range = format!("{}..{}", search_date(from), search_date(to));
+    let mut page_url = Some(api.url(&format!("/search/{}?q={}&per_page=100", endpoint, query(&range))));
+    let mut items: Vec<T> = Vec::new();
+    let mut first_page = true;
+
+    while let Some(url) = page_url {
+        log::debug!("Searching {}", url);
+        let (body, next) = fetch_search_page(api, &url)?;
+        let page: SearchPage<T> = serde_json::from_value(body)?;
+        if first_page && page.total_count > MAX_SEARCH_RESULTS {
+            if to - from > chrono::Duration::seconds(1) {
+                log::debug!("{} results for {}, splitting the range", page.total_count, range);
+                let middle = from + (to - from) / 2;
+                let mut items = search_range(api, endpoint, query, from, middle)?;
+                items.extend(search_range(api, endpoint, query, middle + chrono::Duration::seconds(1), to)?);
+                return Ok(items);
+            }
+            log::warn!("More than {} {} results for {}, data is truncated.", MAX_SEARCH_RESULTS, endpoint, range);
+        }
+        first_page = false;
+        items.extend(page.items);
+        page_url = next;
+    }
+    Ok(items)
+}
+
+fn day_key(created_at: &str, tz: Tz) -> Option<String> {
+    match DateTime::parse_from_rfc3339(created_at) {
+        Ok(time) => Some(time.with_timezone(&tz).format("%Y-%m-%d").to_string()),
+        Err(e) => {
+            log::warn!("Failed to parse search result time {}: {}", created_at, e);
+            None
+        }
+    }
+}
+
+fn issue_detail(item: IssueItem) -> EventDetail {
+    let repo = item.repository_url.rsplit("/repos/").next().unwrap_or(&item.repository_url).to_string();
+    let id = format!("search-issue:{}:{}", repo, item.number);
+    let repo = Repository { name: repo };
+    let action = "opened".to_string();
+    match item.pull_request {
+        Some(link) => {
+            let payload = PullRequestPayload { action, number: item.number as i64, pull_request: None };
+            let event = GitHubEvent { id, created_at: item.created_at, repo, payload: Event::PullRequest(payload), raw_payload: None };
+            let detail = PullRequestDetail {
+                number: item.number,
+                title: item.title,
+                body: item.body,
+                state: item.state,
+                merged: link.merged_at.is_some(),
+                html_url: item.html_url,
+            };
+            (event, Vec::new(), Some(detail))
+        }
+        None => {
+            let issue = IssueSummary {
+                number: item.number as i64,
+                title: item.title,
+                state: item.state,
+                html_url: item.html_url,
+                pull_request: None,
+            };
+            let payload = IssuesPayload { action, issue };
+            let event = GitHubEvent { id, created_at: item.created_at, repo, payload: Event::Issues(payload), raw_payload: None };
+            (event, Vec::new(), None)
+        }
+    }
+}
+
+/// Finds PRs, issues and commits authored by `username` in the range using
+/// the Search API, which isn't limited to the last 90 days like the events
+/// feed. Commits are grouped into one push per repository and day.
+pub fn fetch_contributions(
+    api: &ApiClient,
+    username: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+    tz: Tz,
+) -> Result<DailySummaries, Box<dyn Error>> {
+    let mut daily_summaries: DailySummaries = HashMap::new();
+
+    let issue_query = |range: &str| format!("author:{}+created:{}", username, range);
+    let issues: Vec<IssueItem> = search_range(api, "issues", &issue_query, start_date, end_date)?;
+    log::debug!("Search found {} issues and PRs", issues.len());
+    for item in issues {
+        if let Some(day) = day_key(&item.created_at, tz) {
+            daily_summaries.entry(day).or_default().push(issue_detail(item));
+        }
+    }
+
+    let commit_query = |range: &str| format!("author:{}+author-date:{}", username, range);
+    let commits: Vec<CommitItem> = search_range(api, "commits", &commit_query, start_date, end_date)?;
+    log::debug!("Search found {} commits", commits.len());
+    let commits = commits.into_iter().map(|item| {
+        let commit = CommitDetail {
+            sha: item.sha,
+            commit: CommitInfo { message: item.commit.message, author: None },
+            html_url: item.html_url,
+            author: None,
+            stats: None,
+            files: Vec::new(),
+            also_pushed_to: Vec::new(),
+            other_author: None,
+        };
+        (item.repository.full_name, item.commit.author.date, commit)
+    });
+    for (day, pushes) in group_commits("search", commits, tz) {
+        daily_summaries.entry(day).or_default().extend(pushes);
+    }
+
+    Ok(daily_summaries)
+}
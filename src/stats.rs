@@ -0,0 +1,79 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Serialize, Serializer};
+use std::collections::HashSet;
+use std::error::Error;
+use crate::api::{CommitDetail, CommitFile};
+
+/// Files left out of line counts, e.g. lock files and generated code.
+pub struct FileFilter {
+    excluded: GlobSet,
+}
+
+impl FileFilter {
+    pub fn new(patterns: &[String]) -> Result<FileFilter, Box<dyn Error>> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        Ok(FileFilter { excluded: builder.build()? })
+    }
+
+    /// Patterns are matched against the whole path and the file name, so
+    /// `Cargo.lock` also excludes `crates/foo/Cargo.lock`.
+    pub fn is_excluded(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.excluded.is_match(path) || self.excluded.is_match(name)
+    }
+}
+
+/// Lines added and removed by one commit, and the files it touched.
+pub struct CommitLines<'a> {
+    pub additions: u64,
+    pub deletions: u64,
+    pub files: Vec<&'a CommitFile>,
+}
+
+/// Line counts for a commit, without excluded files. `None` when the
+/// commit came from a source that doesn't report them.
+pub fn commit_lines<'a>(commit: &'a CommitDetail, filter: &FileFilter) -> Option<CommitLines<'a>> {
+    if commit.files.is_empty() {
+        // Nothing to filter, but merges and huge commits still have stats.
+        let stats = commit.stats.as_ref()?;
+        return Some(CommitLines { additions: stats.additions, deletions: stats.deletions, files: Vec::new() });
+    }
+    let mut lines = CommitLines { additions: 0, deletions: 0, files: Vec::new() };
+    for file in commit.files.iter().filter(|file| !filter.is_excluded(&file.filename)) {
+        lines.additions += file.additions;
+        lines.deletions += file.deletions;
+        lines.files.push(file);
+    }
+    Some(lines)
+}
+
+/// Lines changed over a set of commits.
+#[derive(Serialize, Debug, Default)]
+pub struct LineTotals {
+    pub additions: u64,
+    pub deletions: u64,
+    /// Files are counted once however many commits touched them.
+    #[serde(serialize_with = "count")]
+    files: HashSet<(String, String)>,
+    pub commits: usize,
+}
+
+fn count<S: Serializer>(files: &HashSet<(String, String)>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(files.len() as u64)
+}
+
+impl LineTotals {
+    pub fn add<'a>(&mut self, repo: &str, additions: u64, deletions: u64, paths: impl IntoIterator<Item = &'a str>) {
+        self.additions += additions;
+        self.deletions += deletions;
+        self.commits += 1;
+        self.files.extend(paths.into_iter().map(|path| (repo.to_string(), path.to_string())));
+    }
+
+    pub fn files(&self) -> usize {
+        self.files.len()
+    }
+}
@@ -1,110 +1,372 @@
-use chrono::{DateTime, Utc, NaiveDate, Datelike};
-use std::collections::HashMap;
+use chrono::{NaiveDate, Datelike, Duration};
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
-use crate::api::{GitHubEvent, CommitDetail, PullRequestDetail};
+use crate::payload::Event;
+use crate::report::{LanguageLines, Report, ReportEvent, Totals};
 
-pub fn print_summaries(
-    daily_summaries: HashMap<String, Vec<(GitHubEvent, Vec<CommitDetail>, Option<PullRequestDetail>)>>,
-    start_date: DateTime<Utc>,
-    end_date: DateTime<Utc>,
-) -> Result<(), Box<dyn Error>> {
-    println!("# GitHub Contributions Summary\n");
-    println!("*Date Range: {} to {}*\n", start_date.format("%Y-%m-%d"), end_date.format("%Y-%m-%d"));
+/// How events are split into sections after the summary.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    /// One section per day
+    Day,
+    /// One section per week, starting on Monday
+    Week,
+    /// One section per repository
+    Repo,
+    /// One section per organization or user that owns the repositories
+    Org,
+}
 
-    // Calculate overall summary stats
-    let total_events: usize = daily_summaries.values().map(|events| events.len()).sum();
-    let active_days = daily_summaries.len();
-    let mut event_types: HashMap<String, usize> = HashMap::new();
-    for events in daily_summaries.values() {
-        for (event, _, _) in events {
-            *event_types.entry(event.event_type.clone()).or_insert(0) += 1;
-        }
+/// Headings that split a day-by-day report into months or weeks.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subheadings {
+    Month,
+    Week,
+}
+
+/// How the Markdown report is laid out.
+pub struct Layout {
+    pub group_by: GroupBy,
+    /// Month or week headings between days, when grouping by day.
+    pub subheadings: Option<Subheadings>,
+}
+
+/// Comment bodies are cut to this many characters.
+pub const COMMENT_PREVIEW_CHARS: usize = 120;
+
+/// The first `max` characters of `text` on one line, with an ellipsis if
+/// anything was cut.
+pub fn preview(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text,
     }
+}
 
-    // Print overall summary
-    println!("## Summary");
-    println!("- **Total Events**: {}", total_events);
-    println!("- **Active Days**: {}", active_days);
-    let event_type_summary = event_types.iter()
+/// How a review's state reads in the report.
+pub fn review_outcome(state: &str) -> String {
+    state.replace('_', " ")
+}
+
+/// Counts like `2 approved, 1 changes requested`, most common first.
+fn format_outcomes(outcomes: &BTreeMap<String, usize>) -> String {
+    let mut outcomes: Vec<(&String, &usize)> = outcomes.iter().collect();
+    outcomes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    outcomes.iter()
+        .map(|(outcome, count)| format!("{} {}", count, review_outcome(outcome)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Prints how many reviews were given in the period, per repository and
+/// per outcome. Prints nothing when there were no reviews.
+fn print_reviews_given(report: &Report) {
+    if report.totals.reviews.is_empty() {
+        return;
+    }
+    let mut repos: Vec<(&str, Totals)> = by_repository(report)
+        .into_iter()
+        .map(|(repo, events)| (repo, Totals::of(events)))
+        .filter(|(_, totals)| !totals.reviews.is_empty())
+        .collect();
+    let review_count = |totals: &Totals| totals.reviews.values().sum::<usize>();
+    repos.sort_by(|a, b| review_count(&b.1).cmp(&review_count(&a.1)).then(a.0.cmp(b.0)));
+
+    println!("## Reviews Given");
+    println!("- **Total**: {} ({})", review_count(&report.totals), format_outcomes(&report.totals.reviews));
+    for (repo, totals) in repos {
+        println!("- `{}`: {} ({})", repo, review_count(&totals), format_outcomes(&totals.reviews));
+    }
+    println!();
+}
+
+/// `part` as a whole-number percentage of `total`.
+fn percent(part: u64, total: u64) -> u64 {
+    (part * 100 + total / 2) / total.max(1)
+}
+
+/// Languages like `Rust 120 (80%), TOML 30 (20%)`.
+fn format_languages(languages: &[LanguageLines]) -> String {
+    let total: u64 = languages.iter().map(|language| language.lines).sum();
+    languages.iter()
+        .map(|language| format!("{} {} ({}%)", language.language, language.lines, percent(language.lines, total)))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Prints changed lines per language for the range. Prints nothing when
+/// no commit had file lists.
+fn print_languages(languages: &[LanguageLines]) {
+    if languages.is_empty() {
+        return;
+    }
+    let total: u64 = languages.iter().map(|language| language.lines).sum();
+    println!("## Languages");
+    for language in languages {
+        println!("- **{}**: {} lines ({}%)", language.language, language.lines, percent(language.lines, total));
+    }
+    println!();
+}
+
+/// Counts like `3 PushEvent, 1 IssuesEvent`.
+fn format_event_types(event_types: &BTreeMap<String, usize>) -> String {
+    event_types.iter()
         .map(|(type_name, count)| format!("{} {}", count, type_name))
         .collect::<Vec<String>>()
-        .join(", ");
-    println!("- **Event Types**: {}", if event_type_summary.is_empty() { "None".to_string() } else { event_type_summary });
+        .join(", ")
+}
+
+/// The bullets every section starts with, after its own.
+fn print_totals(totals: &Totals) {
+    println!("- **Event Types**: {}", format_event_types(&totals.event_types));
+    if let Some(lines) = &totals.lines {
+        println!("- **Lines Changed**: +{}/-{} ({} files, {} commits)",
+            lines.additions, lines.deletions, lines.files(), lines.commits);
+    }
+    if !totals.languages.is_empty() {
+        println!("- **Languages**: {}", format_languages(&totals.languages));
+    }
     println!();
+}
 
-    let days_in_month = (end_date - start_date).num_days() as u32 + 1;
-    log::debug!("Days in range: {}", days_in_month);
-
-    for day in 1..=days_in_month {
-        let current_date = NaiveDate::from_ymd_opt(start_date.year(), start_date.month(), day);
-        if let Some(date) = current_date {
-            let date_str = date.format("%Y-%m-%d").to_string();
-            log::debug!("Checking day: {}", date_str);
-
-            if let Some(events) = daily_summaries.get(&date_str) {
-                let mut sorted_events: Vec<&(GitHubEvent, Vec<CommitDetail>, Option<PullRequestDetail>)> = events.iter().collect();
-                sorted_events.sort_by(|a, b| a.0.created_at.cmp(&b.0.created_at));
-                log::debug!("Events for {}: {:?}", date_str, sorted_events);
-
-                let start_time = DateTime::parse_from_rfc3339(&sorted_events[0].0.created_at)?
-                    .format("%H:%M:%S UTC");
-                let end_time = DateTime::parse_from_rfc3339(&sorted_events.last().unwrap().0.created_at)?
-                    .format("%H:%M:%S UTC");
-
-                // Calculate daily event type counts
-                let mut daily_event_types: HashMap<String, usize> = HashMap::new();
-                for (event, _, _) in events {
-                    *daily_event_types.entry(event.event_type.clone()).or_insert(0) += 1;
-                }
-                let daily_event_summary = daily_event_types.iter()
-                    .map(|(type_name, count)| format!("{} {}", count, type_name))
-                    .collect::<Vec<String>>()
-                    .join(", ");
-
-                println!("## {}\n", date_str);
-                println!("- **Start Time**: {}", start_time);
-                println!("- **End Time**: {}", end_time);
-                println!("- **Contributions**: {} event(s)", events.len());
-                println!("- **Event Types**: {}", daily_event_summary);
-                println!();
-
-                for (event, commits, pr_detail) in events {
-                    println!("- **{}** - `{}`", event.event_type, event.repo.name);
-                    if event.event_type == "PushEvent" && !commits.is_empty() {
-                        for commit in commits {
-                            let message_lines = commit.commit.message.split('\n');
-                            let mut first_line = true;
-                            for line in message_lines {
-                                if first_line {
-                                    println!("  - Commit `{}`: {}", commit.sha, line);
-                                    first_line = false;
-                                } else {
-                                    println!("    {}", line);
-                                }
-                            }
-                        }
-                    } else if event.event_type == "PullRequestEvent" && pr_detail.is_some() {
-                        let pr = pr_detail.as_ref().unwrap();
-                        let action = event.payload.get("action").and_then(|v| v.as_str()).unwrap_or("unknown");
-                        println!("  - PR [#{}]({}): {} (Action: {}, State: {}, Merged: {})",
-                            pr.number, pr.html_url, pr.title, action, pr.state, pr.merged);
-                    } else if event.event_type == "CreateEvent" {
-                        let ref_name = event.payload.get("ref").and_then(|v| v.as_str()).unwrap_or("none");
-                        let ref_type = event.payload.get("ref_type").and_then(|v| v.as_str()).unwrap_or("unknown");
-                        println!("  - Created {}: `{}`", ref_type, ref_name);
-                    } else if event.event_type == "DeleteEvent" {
-                        let ref_name = event.payload.get("ref").and_then(|v| v.as_str()).unwrap_or("none");
-                        let ref_type = event.payload.get("ref_type").and_then(|v| v.as_str()).unwrap_or("unknown");
-                        println!("  - Deleted {}: `{}`", ref_type, ref_name);
+/// `Week of 2024-02-12 (2024-W07)` for any day in that week.
+fn week_title(date: NaiveDate) -> String {
+    let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+    format!("Week of {} ({})", monday.format("%Y-%m-%d"), monday.format("%G-W%V"))
+}
+
+/// The account that owns a repository, e.g. `rust-lang` for `rust-lang/rust`.
+fn owner(repo: &str) -> &str {
+    repo.split_once('/').map(|(owner, _)| owner).unwrap_or(repo)
+}
+
+/// Every event in the report, by repository.
+fn by_repository(report: &Report) -> HashMap<&str, Vec<&ReportEvent>> {
+    let mut repos: HashMap<&str, Vec<&ReportEvent>> = HashMap::new();
+    for event in report.days.iter().flat_map(|day| &day.events) {
+        repos.entry(&event.repo).or_default().push(event);
+    }
+    repos
+}
+
+/// Prints a table of events and commits per repository, busiest first.
+fn print_repository_table(report: &Report) {
+    let mut repos: Vec<(&str, Totals)> = by_repository(report)
+        .into_iter()
+        .map(|(repo, events)| (repo, Totals::of(events)))
+        .collect();
+    if repos.is_empty() {
+        return;
+    }
+    repos.sort_by(|a, b| b.1.events.cmp(&a.1.events).then(a.0.cmp(b.0)));
+
+    println!("## Repositories\n");
+    println!("| Repository | Events | Commits |");
+    println!("|---|---:|---:|");
+    for (repo, totals) in repos {
+        println!("| `{}` | {} | {} |", repo, totals.events, totals.commits);
+    }
+    println!();
+}
+
+/// Prints one week, repository or organization: its totals, then every
+/// event in it oldest first, each labelled with its day.
+fn print_group(title: &str, events: &[&ReportEvent]) {
+    let totals = Totals::of(events.iter().copied());
+    println!("## {}\n", title);
+    println!("- **Contributions**: {} event(s)", totals.events);
+    println!("- **Active Days**: {}", totals.active_days);
+    print_totals(&totals);
+
+    for event in events {
+        print_event(event, true);
+    }
+}
+
+/// Prints the report split into weeks, repositories or organizations.
+fn print_groups(report: &Report, group_by: GroupBy) -> Result<(), Box<dyn Error>> {
+    if matches!(group_by, GroupBy::Repo | GroupBy::Org) {
+        print_repository_table(report);
+    }
+    let mut groups: BTreeMap<String, Vec<&ReportEvent>> = BTreeMap::new();
+    for day in &report.days {
+        let week = week_title(NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")?);
+        for event in &day.events {
+            let title = match group_by {
+                GroupBy::Week => week.clone(),
+                GroupBy::Repo => format!("`{}`", event.repo),
+                GroupBy::Org => format!("`{}`", owner(&event.repo)),
+                GroupBy::Day => day.date.clone(),
+            };
+            groups.entry(title).or_default().push(event);
+        }
+    }
+    let mut groups: Vec<(String, Vec<&ReportEvent>)> = groups.into_iter().collect();
+    // Weeks read best in order, repositories and organizations busiest first.
+    if group_by != GroupBy::Week {
+        groups.sort_by_key(|(_, events)| std::cmp::Reverse(events.len()));
+    }
+    for (title, events) in groups {
+        print_group(&title, &events);
+    }
+    Ok(())
+}
+
+/// Prints the report one day at a time, with optional month or week
+/// headings in between.
+fn print_days(report: &Report, subheadings: Option<Subheadings>) -> Result<(), Box<dyn Error>> {
+    let day_level = if subheadings.is_some() { "###" } else { "##" };
+    let mut current_subheading: Option<String> = None;
+    for day in &report.days {
+        if let Some(subheadings) = subheadings {
+            let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")?;
+            let subheading = match subheadings {
+                Subheadings::Month => date.format("%B %Y").to_string(),
+                Subheadings::Week => week_title(date),
+            };
+            if current_subheading.as_ref() != Some(&subheading) {
+                println!("## {}\n", subheading);
+                current_subheading = Some(subheading);
+            }
+        }
+
+        // Days only exist in the report when they have events.
+        let (Some(first), Some(last)) = (day.events.first(), day.events.last()) else { continue };
+        println!("{} {}\n", day_level, day.date);
+        println!("- **Start Time**: {}", first.time.format("%H:%M:%S %Z"));
+        println!("- **End Time**: {}", last.time.format("%H:%M:%S %Z"));
+        println!("- **Contributions**: {} event(s)", day.totals.events);
+        print_totals(&day.totals);
+
+        for event in &day.events {
+            print_event(event, false);
+        }
+    }
+    Ok(())
+}
+
+/// `show_date` labels the event with its day, for sections that aren't a
+/// single day.
+fn print_event(event: &ReportEvent, show_date: bool) {
+    if show_date {
+        println!("- **{}** - `{}` ({})", event.event_type, event.repo, event.date);
+    } else {
+        println!("- **{}** - `{}`", event.event_type, event.repo);
+    }
+    match &event.payload {
+        Event::Push(_) if !event.commits.is_empty() => {
+            for commit in &event.commits {
+                let lines = commit.stats.as_ref()
+                    .map(|stats| format!(" +{}/-{} ({} files)", stats.additions, stats.deletions, commit.files.len()))
+                    .unwrap_or_default();
+                let by = commit.other_author.as_ref().map(|name| format!(" (by {})", name)).unwrap_or_default();
+                let message_lines = commit.message.split('\n');
+                let mut first_line = true;
+                for line in message_lines {
+                    if first_line {
+                        println!("  - Commit `{}`{}{}: {}", commit.sha, by, lines, line);
+                        first_line = false;
+                    } else {
+                        println!("    {}", line);
                     }
-                    println!(); // Empty line for spacing between events
                 }
-            } else {
-                log::debug!("No events found for {}", date_str);
+                if !commit.also_pushed_to.is_empty() {
+                    let branches: Vec<String> = commit.also_pushed_to.iter().map(|branch| format!("`{}`", branch)).collect();
+                    println!("    (also pushed to {} {})",
+                        if branches.len() == 1 { "branch" } else { "branches" }, branches.join(", "));
+                }
+            }
+        }
+        Event::Push(push) => {
+            println!("  - {} commit(s)", push.size);
+        }
+        Event::PullRequest(pull_request) => {
+            if let Some(pr) = &event.pull_request {
+                println!("  - PR [#{}]({}): {} (Action: {}, State: {}, Merged: {})",
+                    pr.number, pr.url, pr.title, pull_request.action, pr.state, pr.merged);
+            }
+        }
+        Event::Create(created) => {
+            println!("  - Created {}: `{}`", created.ref_type, created.git_ref.as_deref().unwrap_or("none"));
+        }
+        Event::Delete(deleted) => {
+            println!("  - Deleted {}: `{}`", deleted.ref_type, deleted.git_ref.as_deref().unwrap_or("none"));
+        }
+        Event::Issues(issues) => {
+            let issue = &issues.issue;
+            println!("  - Issue [#{}]({}): {} (Action: {}, State: {})",
+                issue.number, issue.html_url, issue.title, issues.action, issue.state);
+        }
+        Event::IssueComment(comment) => {
+            let issue = &comment.issue;
+            let kind = if issue.pull_request.is_some() { "PR" } else { "issue" };
+            println!("  - Commented on {} [#{}]({}): {}", kind, issue.number, issue.html_url, issue.title);
+            println!("    > {} ([comment]({}))", preview(&comment.comment.body, COMMENT_PREVIEW_CHARS), comment.comment.html_url);
+        }
+        Event::PullRequestReview(review) => {
+            let pr = &review.pull_request;
+            println!("  - Reviewed PR [#{}]({}): {} (Outcome: {}, Inline comments: {})",
+                pr.number, pr.html_url, pr.title, review_outcome(&review.review.state), event.review_comments.unwrap_or(0));
+        }
+        Event::PullRequestReviewComment(comment) => {
+            let pr = &comment.pull_request;
+            println!("  - Review comment on PR [#{}]({}): {}", pr.number, pr.html_url, pr.title);
+            println!("    > `{}`: {} ([comment]({}))",
+                comment.comment.path, preview(&comment.comment.body, COMMENT_PREVIEW_CHARS), comment.comment.html_url);
+        }
+        Event::Release(release) => {
+            let name = release.release.name.as_deref().filter(|name| !name.is_empty()).unwrap_or(&release.release.tag_name);
+            println!("  - Release [`{}`]({}): {} (Action: {})",
+                release.release.tag_name, release.release.html_url, name, release.action);
+        }
+        Event::Fork(fork) => {
+            println!("  - Forked to [`{}`]({})", fork.forkee.full_name, fork.forkee.html_url);
+        }
+        Event::Watch(_) => {
+            println!("  - Starred `{}`", event.repo);
+        }
+        Event::Member(member) => {
+            println!("  - Collaborator [{}]({}) {}", member.member.login, member.member.html_url, member.action);
+        }
+        Event::Public => {
+            println!("  - Made `{}` public", event.repo);
+        }
+        Event::Gollum(gollum) => {
+            for page in &gollum.pages {
+                let action = if page.action == "created" { "Created" } else { "Edited" };
+                println!("  - {} wiki page [{}]({})", action, page.title, page.html_url);
             }
-        } else {
-            log::error!("Invalid date generated for day: {}", day);
         }
+        _ => {}
     }
-    Ok(())
-}
\ No newline at end of file
+    println!(); // Empty line for spacing between events
+}
+
+/// Prints the report as Markdown.
+pub fn print_summaries(report: &Report, layout: &Layout) -> Result<(), Box<dyn Error>> {
+    println!("# GitHub Contributions Summary\n");
+    println!("*Date Range: {} to {} ({})*\n", report.range.start, report.range.end, report.range.timezone);
+
+    // Print overall summary
+    let totals = &report.totals;
+    println!("## Summary");
+    println!("- **Total Events**: {}", totals.events);
+    println!("- **Active Days**: {}", totals.active_days);
+    let event_type_summary = format_event_types(&totals.event_types);
+    println!("- **Event Types**: {}", if event_type_summary.is_empty() { "None".to_string() } else { event_type_summary });
+    if let Some(lines) = &totals.lines {
+        println!("- **Lines Changed**: +{}/-{} ({} files, {} commits)",
+            lines.additions, lines.deletions, lines.files(), lines.commits);
+    }
+    println!();
+
+    print_languages(&totals.languages);
+    print_reviews_given(report);
+
+    match layout.group_by {
+        GroupBy::Day => print_days(report, layout.subheadings),
+        group_by => print_groups(report, group_by),
+    }
+}